@@ -0,0 +1,276 @@
+use serde::de::DeserializeOwned;
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.{}", name, suffix))
+}
+
+fn create_parent(path: &Path) -> Result<(), String> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e)),
+        _ => Ok(()),
+    }
+}
+
+pub fn temp_path(path: &Path) -> PathBuf {
+    let count = TEMP_COUNTER.fetch_add(1, Ordering::Relaxed);
+    sibling(path, &format!("{}.{}.tmp", std::process::id(), count))
+}
+
+fn resolve(path: &Path) -> PathBuf {
+    if let Ok(real) = fs::canonicalize(path) {
+        return real;
+    }
+    match (fs::read_link(path), path.parent()) {
+        (Ok(target), Some(parent)) => parent.join(target),
+        _ => path.to_path_buf(),
+    }
+}
+
+pub fn write(path: &Path, contents: &[u8]) -> Result<(), String> {
+    let path = &resolve(path);
+    create_parent(path)?;
+    let temp = temp_path(path);
+    let permissions = fs::metadata(path)
+        .ok()
+        .map(|metadata| metadata.permissions());
+
+    let result = File::create(&temp)
+        .and_then(|mut file| {
+            if let Some(permissions) = permissions {
+                file.set_permissions(permissions)?;
+            }
+            file.write_all(contents)?;
+            file.sync_all()
+        })
+        .and_then(|_| fs::rename(&temp, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result.map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+pub fn replace_symlink(link: &Path, target: &str) -> Result<(), String> {
+    create_parent(link)?;
+    let temp = temp_path(link);
+    symlink(target, &temp).map_err(|e| format!("Failed to create {}: {}", temp.display(), e))?;
+    fs::rename(&temp, link).map_err(|e| format!("Failed to replace {}: {}", link.display(), e))
+}
+
+pub fn load_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+pub fn recover_json<T: DeserializeOwned + Default>(path: &Path) -> Result<T, String> {
+    load_json(path).or_else(|e| {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let backup = PathBuf::from(format!("{}.{}.bak", path.display(), timestamp));
+        fs::copy(path, &backup)
+            .map_err(|copy_error| format!("{} (backup failed: {})", e, copy_error))?;
+        eprintln!("{}, saved a copy to {}", e, backup.display());
+        Ok(T::default())
+    })
+}
+
+fn acquire(path: &Path) -> Result<File, String> {
+    let path = &resolve(path);
+    create_parent(path)?;
+    let lock_path = sibling(path, "lock");
+    let lock = OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)
+        .map_err(|e| format!("Failed to open {}: {}", lock_path.display(), e))?;
+    lock.lock()
+        .map_err(|e| format!("Failed to lock {}: {}", lock_path.display(), e))?;
+    Ok(lock)
+}
+
+pub fn with_lock<T>(path: &Path, f: impl FnOnce() -> T) -> T {
+    let lock = acquire(path)
+        .map_err(|e| eprintln!("{}, writing without a lock", e))
+        .ok();
+
+    let result = f();
+    if let Some(lock) = lock {
+        let _ = lock.unlock();
+    }
+    result
+}
+
+pub fn update(path: &Path, f: impl FnOnce(&str) -> Option<String>) -> Result<(), String> {
+    with_lock(path, || {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+        match f(&contents) {
+            Some(updated) if updated != contents => write(path, updated.as_bytes()),
+            _ => Ok(()),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use std::process::{Command, Stdio};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+    use std::thread;
+
+    const THREADS: usize = 8;
+    const PROCESSES: usize = 4;
+    const INCREMENTS: usize = 50;
+    const CHILD_ENV: &str = "HYPRWALL_ATOMIC_TEST_FILE";
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("hyprwall-atomic-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn increment(path: &Path) {
+        for _ in 0..INCREMENTS {
+            update(path, |contents| {
+                let count: usize = contents.trim().parse().expect("counter is not a number");
+                Some((count + 1).to_string())
+            })
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn child_increments() {
+        if let Ok(path) = std::env::var(CHILD_ENV) {
+            increment(Path::new(&path));
+        }
+    }
+
+    #[test]
+    fn concurrent_updates_are_not_lost() {
+        let dir = test_dir("counter");
+        let path = dir.join("counter");
+        write(&path, b"0").unwrap();
+
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let path = path.clone();
+            let done = Arc::clone(&done);
+            thread::spawn(move || {
+                while !done.load(Ordering::Relaxed) {
+                    let contents = fs::read_to_string(&path).unwrap();
+                    assert!(
+                        contents.trim().parse::<usize>().is_ok(),
+                        "read a partial file: {:?}",
+                        contents
+                    );
+                }
+            })
+        };
+
+        let children: Vec<_> = (0..PROCESSES)
+            .map(|_| {
+                Command::new(std::env::current_exe().unwrap())
+                    .args(["--exact", "atomic::tests::child_increments"])
+                    .env(CHILD_ENV, &path)
+                    .stdout(Stdio::null())
+                    .spawn()
+                    .unwrap()
+            })
+            .collect();
+        let threads: Vec<_> = (0..THREADS)
+            .map(|_| {
+                let path = path.clone();
+                thread::spawn(move || increment(&path))
+            })
+            .collect();
+
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        for mut child in children {
+            assert!(child.wait().unwrap().success());
+        }
+        done.store(true, Ordering::Relaxed);
+        reader.join().unwrap();
+
+        let count: usize = fs::read_to_string(&path).unwrap().parse().unwrap();
+        assert_eq!(count, (THREADS + PROCESSES) * INCREMENTS);
+
+        let leftovers: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().into_owned())
+            .filter(|name| name.ends_with(".tmp"))
+            .collect();
+        assert!(leftovers.is_empty(), "temp files left: {:?}", leftovers);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn writes_through_symlinks() {
+        let dir = test_dir("symlink");
+        let target = dir.join("target.ini");
+        let link = dir.join("link.ini");
+        fs::write(&target, "old").unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        symlink(&target, &link).unwrap();
+
+        write(&link, b"new").unwrap();
+
+        assert!(link.is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "new");
+        assert_eq!(
+            fs::metadata(&target).unwrap().permissions().mode() & 0o777,
+            0o600
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn unreadable_files_are_not_replaced() {
+        let dir = test_dir("unreadable");
+        let path = dir.join("config.ini");
+        fs::write(&path, b"# caf\xe9\nkey = value\n").unwrap();
+
+        assert!(update(&path, |_| Some("key = other\n".to_string())).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"# caf\xe9\nkey = value\n");
+
+        update(&dir.join("missing"), |contents| {
+            assert!(contents.is_empty());
+            Some("created".to_string())
+        })
+        .unwrap();
+        assert_eq!(fs::read_to_string(dir.join("missing")).unwrap(), "created");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
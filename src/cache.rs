@@ -0,0 +1,141 @@
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::collections::hash_map::DefaultHasher;
+use std::fs::{self, File};
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{atomic, config};
+
+const DEFAULT_CACHE_SIZE: u64 = 1024;
+
+lazy_static! {
+    static ref CACHE_USAGE: Mutex<Option<u64>> = Mutex::new(None);
+}
+
+fn cache_root() -> PathBuf {
+    std::env::var("XDG_CACHE_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(shellexpand::tilde("~/.cache").into_owned()))
+        .join("hyprwall")
+}
+
+fn cache_limit() -> u64 {
+    config::get("cache_size")
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(DEFAULT_CACHE_SIZE)
+        * 1024
+        * 1024
+}
+
+fn touch(path: &Path) {
+    let _ = File::options()
+        .write(true)
+        .open(path)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+}
+
+fn prune(limit: u64) -> u64 {
+    let Ok(kinds) = fs::read_dir(cache_root()) else {
+        return 0;
+    };
+
+    let mut entries: Vec<(SystemTime, u64, PathBuf)> = kinds
+        .flatten()
+        .filter_map(|kind| fs::read_dir(kind.path()).ok())
+        .flat_map(|files| files.flatten())
+        .filter(|file| !file.file_name().to_string_lossy().starts_with('.'))
+        .filter_map(|file| {
+            let metadata = file.metadata().ok().filter(|m| m.is_file())?;
+            Some((metadata.modified().ok()?, metadata.len(), file.path()))
+        })
+        .collect();
+
+    let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+    if total <= limit {
+        return total;
+    }
+
+    entries.sort_by_key(|(used, _, _)| *used);
+    for (_, size, path) in entries {
+        if total <= limit {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= size;
+        }
+    }
+    total
+}
+
+fn record_usage(size: u64) {
+    let limit = cache_limit();
+    let mut usage = CACHE_USAGE.lock();
+    let total = match *usage {
+        Some(total) if total + size <= limit => total + size,
+        Some(_) => prune(limit / 10 * 9),
+        None => prune(limit),
+    };
+    *usage = Some(total);
+}
+
+pub fn cache_dir(kind: &str) -> Result<PathBuf, String> {
+    let dir = cache_root().join(kind);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    Ok(dir)
+}
+
+pub fn content_hash(path: &Path) -> Result<String, String> {
+    let cached = get_or_create("hashes", &file_key(path)?, "txt", |target| {
+        let contents =
+            fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut hasher = DefaultHasher::new();
+        hasher.write(&contents);
+        fs::write(target, format!("{:016x}", hasher.finish()))
+            .map_err(|e| format!("Failed to save the hash of {}: {}", path.display(), e))
+    })?;
+
+    fs::read_to_string(&cached)
+        .map(|hash| hash.trim().to_string())
+        .map_err(|e| format!("Failed to read {}: {}", cached, e))
+}
+
+pub fn file_key(path: &Path) -> Result<String, String> {
+    let metadata =
+        fs::metadata(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |time| time.as_nanos());
+
+    let mut hasher = DefaultHasher::new();
+    hasher.write(path.as_os_str().as_encoded_bytes());
+    hasher.write_u128(modified);
+    hasher.write_u64(metadata.len());
+    Ok(format!("{:016x}", hasher.finish()))
+}
+
+pub fn get_or_create(
+    kind: &str,
+    key: &str,
+    extension: &str,
+    create: impl FnOnce(&Path) -> Result<(), String>,
+) -> Result<String, String> {
+    let target = cache_dir(kind)?.join(format!("{}.{}", key, extension));
+
+    if target.exists() {
+        touch(&target);
+    } else {
+        let temp = atomic::temp_path(&target);
+        create(&temp)?;
+        fs::rename(&temp, &target)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+        record_usage(fs::metadata(&target).map_or(0, |metadata| metadata.len()));
+    }
+
+    Ok(target.to_string_lossy().into_owned())
+}
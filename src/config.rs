@@ -0,0 +1,168 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::SystemTime;
+
+use crate::{atomic, state};
+
+pub const CONFIG_FILE: &str = "~/.config/hyprwall/config.ini";
+const SETTINGS_SECTION: &str = "Settings";
+
+pub fn config_path() -> String {
+    shellexpand::tilde(CONFIG_FILE).into_owned()
+}
+
+fn read_config() -> String {
+    match fs::read(config_path()) {
+        Ok(contents) => String::from_utf8_lossy(&contents).into_owned(),
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            eprintln!("Error reading config: {}", e);
+            String::new()
+        }
+    }
+}
+
+fn update_config(f: impl FnOnce(&str) -> Option<String>) {
+    let result = atomic::update(Path::new(&config_path()), |contents| {
+        f(contents).map(|updated| format!("{}\n", updated.trim_end()))
+    });
+    if let Err(e) = result {
+        eprintln!("Error saving config: {}", e);
+    }
+}
+
+fn section_header(line: &str) -> Option<&str> {
+    let line = line.trim();
+    line.strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .map(str::trim)
+}
+
+fn parse_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+        return None;
+    }
+    line.split_once('=').map(|(k, v)| (k.trim(), v.trim()))
+}
+
+pub fn section(name: &str) -> Vec<(String, String)> {
+    let contents = read_config();
+    let mut current = SETTINGS_SECTION;
+    let mut entries = Vec::new();
+
+    for line in contents.lines() {
+        if let Some(header) = section_header(line) {
+            current = header;
+        } else if current == name {
+            if let Some((key, value)) = parse_line(line) {
+                entries.push((key.to_string(), value.to_string()));
+            }
+        }
+    }
+
+    entries
+}
+
+pub fn sections() -> Vec<String> {
+    read_config()
+        .lines()
+        .filter_map(section_header)
+        .map(String::from)
+        .collect()
+}
+
+pub fn settings() -> BTreeMap<String, String> {
+    let mut settings: BTreeMap<String, String> = section(SETTINGS_SECTION).into_iter().collect();
+    settings.extend(state::profile_settings());
+    settings
+}
+
+pub fn modified() -> Option<SystemTime> {
+    fs::metadata(config_path())
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+pub fn get(key: &str) -> Option<String> {
+    if state::PROFILE_KEYS.contains(&key) {
+        if let Some(value) = state::profile_settings().remove(key) {
+            return Some(value);
+        }
+    }
+    section(SETTINGS_SECTION)
+        .into_iter()
+        .find(|(k, _)| k == key)
+        .map(|(_, v)| v)
+}
+
+pub fn set(key: &str, value: &str) {
+    if state::PROFILE_KEYS.contains(&key) {
+        state::clear_profile_setting(key);
+    }
+    update_config(|contents| {
+        let mut lines: Vec<String> = contents.lines().map(String::from).collect();
+        let new_line = format!("{} = {}", key, value);
+
+        let mut current = SETTINGS_SECTION.to_string();
+        let mut settings_end = None;
+        let mut existing = None;
+
+        for (i, line) in lines.iter().enumerate() {
+            if let Some(header) = section_header(line) {
+                current = header.to_string();
+                continue;
+            }
+            if current == SETTINGS_SECTION {
+                if parse_line(line).is_some_and(|(k, _)| k == key) {
+                    existing = Some(i);
+                    break;
+                }
+                if !line.trim().is_empty() {
+                    settings_end = Some(i + 1);
+                }
+            }
+        }
+
+        match existing {
+            Some(pos) if parse_line(&lines[pos]).is_some_and(|(_, v)| v == value) => return None,
+            Some(pos) => lines[pos] = new_line,
+            None => {
+                let pos = settings_end.unwrap_or_else(|| {
+                    lines
+                        .iter()
+                        .position(|line| section_header(line) == Some(SETTINGS_SECTION))
+                        .map_or(0, |p| p + 1)
+                });
+                lines.insert(pos, new_line);
+            }
+        }
+
+        Some(lines.join("\n"))
+    });
+}
+
+pub fn remove(key: &str) {
+    update_config(|contents| {
+        let mut current = SETTINGS_SECTION.to_string();
+        let mut removed = false;
+
+        let lines: Vec<&str> = contents
+            .lines()
+            .filter(|line| {
+                if let Some(header) = section_header(line) {
+                    current = header.to_string();
+                    return true;
+                }
+                let matches =
+                    current == SETTINGS_SECTION && parse_line(line).is_some_and(|(k, _)| k == key);
+                removed |= matches;
+                !matches
+            })
+            .collect();
+
+        removed.then(|| lines.join("\n"))
+    });
+}
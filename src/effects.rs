@@ -0,0 +1,278 @@
+use gtk::gdk_pixbuf::{Colorspace, Pixbuf};
+use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+use crate::{cache, config, formats, library};
+
+const REFERENCE_WIDTH: f64 = 1920.0;
+const DEFAULT_TINT_STRENGTH: f64 = 0.3;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Effects {
+    pub blur: f64,
+    pub dim: f64,
+    pub tint: Option<(u8, u8, u8, f64)>,
+    pub grayscale: bool,
+    pub vignette: f64,
+}
+
+pub fn parse_color(value: &str) -> Option<(u8, u8, u8, f64)> {
+    let (color, strength) = match value.split_once(':') {
+        Some((color, strength)) => (color, strength.trim().parse().ok()?),
+        None => (value, DEFAULT_TINT_STRENGTH),
+    };
+    let hex = color.trim().trim_start_matches('#');
+    if hex.len() != 6 || !(0.0..=1.0).contains(&strength) {
+        return None;
+    }
+
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?, strength))
+}
+
+fn parse_fraction(name: &str, value: &str) -> Result<f64, String> {
+    value
+        .trim()
+        .parse()
+        .ok()
+        .filter(|value| (0.0..=1.0).contains(value))
+        .ok_or_else(|| format!("Invalid {} value: {} (use 0 to 1)", name, value))
+}
+
+impl Effects {
+    pub fn parse(spec: &str) -> Result<Effects, String> {
+        let mut effects = Effects::default();
+
+        for item in spec.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+            let (name, value) = item.split_once('=').unwrap_or((item, ""));
+            match name.trim() {
+                "blur" => {
+                    effects.blur = value
+                        .trim()
+                        .parse()
+                        .ok()
+                        .filter(|blur: &f64| *blur >= 0.0)
+                        .ok_or_else(|| format!("Invalid blur value: {}", value))?;
+                }
+                "dim" => effects.dim = parse_fraction("dim", value)?,
+                "vignette" => effects.vignette = parse_fraction("vignette", value)?,
+                "tint" => {
+                    effects.tint = Some(
+                        parse_color(value)
+                            .ok_or_else(|| format!("Invalid tint color: {}", value))?,
+                    );
+                }
+                "grayscale" => effects.grayscale = true,
+                "none" => {}
+                other => return Err(format!("Unknown effect: {}", other)),
+            }
+        }
+
+        Ok(effects)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        *self == Effects::default()
+    }
+
+    pub fn render(&self, pixbuf: &Pixbuf) -> Option<Pixbuf> {
+        if pixbuf.bits_per_sample() != 8 {
+            return None;
+        }
+
+        let width = pixbuf.width() as usize;
+        let height = pixbuf.height() as usize;
+        let channels = pixbuf.n_channels() as usize;
+        let rowstride = pixbuf.rowstride() as usize;
+        let stride = width * channels;
+
+        let bytes = pixbuf.read_pixel_bytes();
+        let mut pixels: Vec<u8> = (0..height)
+            .flat_map(|y| &bytes[y * rowstride..y * rowstride + stride])
+            .copied()
+            .collect();
+
+        let sigma = self.blur * width as f64 / REFERENCE_WIDTH;
+        if sigma >= 0.5 {
+            gaussian_blur(&mut pixels, width, height, channels, sigma);
+        }
+
+        let (center_x, center_y) = ((width as f64 - 1.0) / 2.0, (height as f64 - 1.0) / 2.0);
+        pixels
+            .par_chunks_mut(stride)
+            .enumerate()
+            .for_each(|(y, row)| {
+                let dy = (y as f64 - center_y) / center_y.max(1.0);
+                for (x, pixel) in row.chunks_mut(channels).enumerate() {
+                    let mut rgb = [pixel[0] as f64, pixel[1] as f64, pixel[2] as f64];
+
+                    if self.grayscale {
+                        let luma = 0.2126 * rgb[0] + 0.7152 * rgb[1] + 0.0722 * rgb[2];
+                        rgb = [luma; 3];
+                    }
+
+                    if let Some((r, g, b, strength)) = self.tint {
+                        for (value, tint) in rgb.iter_mut().zip([r, g, b]) {
+                            *value = *value * (1.0 - strength) + tint as f64 * strength;
+                        }
+                    }
+
+                    let mut factor = 1.0 - self.dim;
+                    if self.vignette > 0.0 {
+                        let dx = (x as f64 - center_x) / center_x.max(1.0);
+                        factor *= 1.0 - self.vignette * ((dx * dx + dy * dy) / 2.0);
+                    }
+
+                    for (channel, value) in pixel.iter_mut().zip(rgb) {
+                        *channel = (value * factor).round().clamp(0.0, 255.0) as u8;
+                    }
+                }
+            });
+
+        Some(Pixbuf::from_bytes(
+            &gtk::glib::Bytes::from_owned(pixels),
+            Colorspace::Rgb,
+            pixbuf.has_alpha(),
+            8,
+            width as i32,
+            height as i32,
+            stride as i32,
+        ))
+    }
+}
+
+impl fmt::Display for Effects {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut items = Vec::new();
+        if self.blur > 0.0 {
+            items.push(format!("blur={}", self.blur));
+        }
+        if self.dim > 0.0 {
+            items.push(format!("dim={}", self.dim));
+        }
+        if let Some((r, g, b, strength)) = self.tint {
+            items.push(format!("tint=#{:02x}{:02x}{:02x}:{}", r, g, b, strength));
+        }
+        if self.grayscale {
+            items.push("grayscale".to_string());
+        }
+        if self.vignette > 0.0 {
+            items.push(format!("vignette={}", self.vignette));
+        }
+
+        if items.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", items.join(","))
+        }
+    }
+}
+
+fn box_sizes(sigma: f64, passes: usize) -> Vec<usize> {
+    let n = passes as f64;
+    let ideal = (12.0 * sigma * sigma / n + 1.0).sqrt();
+    let mut lower = ideal.floor() as usize;
+    if lower.is_multiple_of(2) {
+        lower = lower.saturating_sub(1).max(1);
+    }
+    let l = lower as f64;
+    let m = ((12.0 * sigma * sigma - n * l * l - 4.0 * n * l - 3.0 * n) / (-4.0 * l - 4.0)).round();
+
+    (0..passes)
+        .map(|i| if (i as f64) < m { lower } else { lower + 2 })
+        .collect()
+}
+
+fn box_blur_row(row: &mut [u8], channels: usize, radius: usize) {
+    let width = row.len() / channels;
+    let source = row.to_vec();
+    let window = (2 * radius + 1) as u32;
+    let last = width as isize - 1;
+
+    for c in 0..channels {
+        let at = |i: isize| source[i.clamp(0, last) as usize * channels + c] as u32;
+        let mut sum: u32 = (-(radius as isize)..=radius as isize).map(at).sum();
+        for x in 0..width as isize {
+            row[x as usize * channels + c] = ((sum + window / 2) / window) as u8;
+            sum += at(x + radius as isize + 1);
+            sum -= at(x - radius as isize);
+        }
+    }
+}
+
+fn transpose(pixels: &[u8], width: usize, height: usize, channels: usize) -> Vec<u8> {
+    let mut transposed = vec![0; pixels.len()];
+    transposed
+        .par_chunks_mut(height * channels)
+        .enumerate()
+        .for_each(|(x, row)| {
+            for y in 0..height {
+                let source = (y * width + x) * channels;
+                row[y * channels..(y + 1) * channels]
+                    .copy_from_slice(&pixels[source..source + channels]);
+            }
+        });
+    transposed
+}
+
+fn gaussian_blur(pixels: &mut Vec<u8>, width: usize, height: usize, channels: usize, sigma: f64) {
+    let sizes = box_sizes(sigma, 3);
+    let blur_rows = |pixels: &mut [u8], row_len: usize| {
+        pixels.par_chunks_mut(row_len).for_each(|row| {
+            for size in &sizes {
+                box_blur_row(row, channels, size / 2);
+            }
+        });
+    };
+
+    blur_rows(pixels, width * channels);
+    let mut transposed = transpose(pixels, width, height, channels);
+    blur_rows(&mut transposed, height * channels);
+    *pixels = transpose(&transposed, height, width, channels);
+}
+
+pub fn global() -> Effects {
+    config::get("effects")
+        .and_then(|spec| match Effects::parse(&spec) {
+            Ok(effects) => Some(effects),
+            Err(e) => {
+                eprintln!("Ignoring invalid effects setting: {}", e);
+                None
+            }
+        })
+        .unwrap_or_default()
+}
+
+pub fn for_wallpaper(path: &str) -> Effects {
+    library::load()
+        .effects(path)
+        .and_then(|spec| Effects::parse(spec).ok())
+        .unwrap_or_else(global)
+}
+
+pub fn apply(path: &str, effects: &Effects) -> Result<String, String> {
+    let source = Path::new(path);
+    if effects.is_empty() || formats::sniff(source) == Some(formats::ImageFormat::Gif) {
+        return Ok(path.to_string());
+    }
+
+    let spec = effects.to_string();
+    let mut hasher = DefaultHasher::new();
+    spec.hash(&mut hasher);
+    let key = format!("{}-{:016x}", cache::content_hash(source)?, hasher.finish());
+
+    cache::get_or_create("effects", &key, "png", |target| {
+        println!("Applying effects to {}: {}", path, spec);
+        let pixbuf =
+            Pixbuf::from_file(source).map_err(|e| format!("Failed to load {}: {}", path, e))?;
+        let pixbuf = pixbuf.apply_embedded_orientation().unwrap_or(pixbuf);
+        effects
+            .render(&pixbuf)
+            .ok_or_else(|| format!("Failed to apply effects to {}", path))?
+            .savev(target, "png", &[])
+            .map_err(|e| format!("Failed to save {}: {}", target.display(), e))
+    })
+}
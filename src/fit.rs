@@ -0,0 +1,49 @@
+use crate::{config, WallpaperBackend};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FitMode {
+    Fill,
+    Fit,
+    Center,
+    Tile,
+}
+
+impl FitMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "fill" => Some(FitMode::Fill),
+            "fit" => Some(FitMode::Fit),
+            "center" => Some(FitMode::Center),
+            "tile" => Some(FitMode::Tile),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            FitMode::Fill => "fill",
+            FitMode::Fit => "fit",
+            FitMode::Center => "center",
+            FitMode::Tile => "tile",
+        }
+    }
+
+    pub fn load() -> Self {
+        config::get("fit")
+            .and_then(|fit| FitMode::from_name(&fit))
+            .unwrap_or(FitMode::Fill)
+    }
+
+    pub fn backend_mode(&self, backend: WallpaperBackend) -> &'static str {
+        match (backend, self) {
+            (WallpaperBackend::Hyprpaper, FitMode::Fit) => "contain:",
+            (WallpaperBackend::Hyprpaper, FitMode::Tile) => "tile:",
+            (WallpaperBackend::Swww, FitMode::Fit) => "fit",
+            (WallpaperBackend::Swww, FitMode::Center) => "no",
+            (WallpaperBackend::Swww, FitMode::Fill | FitMode::Tile) => "crop",
+            (WallpaperBackend::Feh, FitMode::Fit) => "max",
+            (WallpaperBackend::Swaybg | WallpaperBackend::Feh, mode) => mode.name(),
+            _ => "",
+        }
+    }
+}
@@ -0,0 +1,129 @@
+use gtk::gdk_pixbuf::Pixbuf;
+use lazy_static::lazy_static;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::{cache, slideshow, WallpaperBackend};
+
+lazy_static! {
+    static ref PIXBUF_MIME_TYPES: Vec<String> = Pixbuf::formats()
+        .iter()
+        .filter(|format| !format.is_disabled())
+        .flat_map(|format| format.mime_types())
+        .map(|mime_type| mime_type.to_string())
+        .collect();
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+    Webp,
+    Avif,
+    Bmp,
+    Tiff,
+    Jxl,
+    Svg,
+}
+
+impl ImageFormat {
+    fn mime_types(&self) -> &'static [&'static str] {
+        match self {
+            ImageFormat::Png => &["image/png"],
+            ImageFormat::Jpeg => &["image/jpeg"],
+            ImageFormat::Gif => &["image/gif"],
+            ImageFormat::Webp => &["image/webp"],
+            ImageFormat::Avif => &["image/avif"],
+            ImageFormat::Bmp => &["image/bmp", "image/x-bmp"],
+            ImageFormat::Tiff => &["image/tiff"],
+            ImageFormat::Jxl => &["image/jxl"],
+            ImageFormat::Svg => &["image/svg+xml", "image/svg"],
+        }
+    }
+
+    pub fn is_decodable(&self) -> bool {
+        PIXBUF_MIME_TYPES
+            .iter()
+            .any(|mime_type| self.mime_types().contains(&mime_type.as_str()))
+    }
+}
+
+pub fn sniff(path: &Path) -> Option<ImageFormat> {
+    let mut header = [0u8; 512];
+    let len = File::open(path).ok()?.read(&mut header).ok()?;
+    let header = &header[..len];
+
+    let format = match header {
+        [0x89, b'P', b'N', b'G', ..] => ImageFormat::Png,
+        [0xFF, 0xD8, 0xFF, ..] => ImageFormat::Jpeg,
+        [b'G', b'I', b'F', b'8', ..] => ImageFormat::Gif,
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => ImageFormat::Webp,
+        [_, _, _, _, b'f', b't', b'y', b'p', b'a', b'v', b'i', b'f' | b's', ..] => {
+            ImageFormat::Avif
+        }
+        [b'B', b'M', ..] => ImageFormat::Bmp,
+        [b'I', b'I', 0x2A, 0x00, ..] | [b'M', b'M', 0x00, 0x2A, ..] => ImageFormat::Tiff,
+        [0xFF, 0x0A, ..] | [0x00, 0x00, 0x00, 0x0C, b'J', b'X', b'L', b' ', ..] => ImageFormat::Jxl,
+        _ if String::from_utf8_lossy(header).contains("<svg") => ImageFormat::Svg,
+        _ => return None,
+    };
+
+    Some(format)
+}
+
+pub fn is_wallpaper(path: &Path) -> bool {
+    if !path.is_file() {
+        return false;
+    }
+    if slideshow::is_slideshow(path) {
+        return slideshow::load(path).is_ok();
+    }
+    sniff(path).is_some_and(|format| format.is_decodable())
+}
+
+pub fn backend_supports(backend: WallpaperBackend, format: ImageFormat) -> bool {
+    use ImageFormat::*;
+
+    match backend {
+        WallpaperBackend::Hyprpaper => matches!(format, Png | Jpeg | Webp),
+        WallpaperBackend::Swaybg => matches!(format, Png | Jpeg),
+        WallpaperBackend::Swww => matches!(format, Png | Jpeg | Gif | Webp | Bmp | Tiff),
+        WallpaperBackend::Feh => matches!(format, Png | Jpeg | Gif | Webp | Bmp | Tiff),
+        WallpaperBackend::Wallutils => matches!(format, Png | Jpeg | Gif),
+        WallpaperBackend::None => false,
+    }
+}
+
+pub fn prepare_for_backend(path: &str, backend: WallpaperBackend) -> Result<String, String> {
+    let source = Path::new(path);
+    let format = sniff(source).ok_or_else(|| format!("Unsupported image format: {}", path))?;
+
+    if backend_supports(backend, format) {
+        return Ok(path.to_string());
+    }
+
+    if !format.is_decodable() {
+        return Err(format!(
+            "No image loader available for {:?} images: {}",
+            format, path
+        ));
+    }
+
+    let key = cache::content_hash(source)?;
+    let converted = cache::get_or_create("converted", &key, "png", |target| {
+        println!(
+            "{} can't display {:?} images, converting {}",
+            backend.name(),
+            format,
+            path
+        );
+        Pixbuf::from_file(source)
+            .map_err(|e| format!("Failed to load {}: {}", path, e))?
+            .savev(target, "png", &[])
+            .map_err(|e| format!("Failed to convert {}: {}", path, e))
+    })?;
+
+    Ok(converted)
+}
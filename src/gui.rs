@@ -1,44 +1,78 @@
-use crossbeam_channel::unbounded;
-use glib::ControlFlow;
+use glib::{BoxedAnyObject, ControlFlow};
 use gtk::{
     gdk::{self, Texture},
     gdk_pixbuf::Pixbuf,
     gio, glib,
     prelude::*,
-    Application, ApplicationWindow, Box as GtkBox, Button, ComboBoxText, EventControllerMotion,
-    FlowBox, FlowBoxChild, Image, MessageDialog, ScrolledWindow, SearchEntry,
+    Application, ApplicationWindow, Box as GtkBox, Button, ComboBoxText, CustomFilter,
+    EventControllerMotion, GridView, Image, ListItem, MessageDialog, ScrolledWindow, SearchEntry,
+    SignalListItemFactory,
 };
 use parking_lot::Mutex;
-use rand::seq::SliceRandom;
 use rayon::prelude::*;
 use std::{
-    cell::RefCell,
-    collections::{BTreeMap, VecDeque},
+    cell::{Cell, RefCell},
+    collections::{BTreeMap, BTreeSet, VecDeque},
     fs,
-    io::{Read, Write},
     path::{Path, PathBuf},
     rc::Rc,
-    sync::atomic::{AtomicBool, Ordering},
     sync::Arc,
+    time::SystemTime,
 };
 
-use crate::WallpaperBackend;
+use crate::{
+    config,
+    effects::{self, Effects},
+    formats::{self, ImageFormat},
+    history, library, profile,
+    reload::{ConfigWatcher, Reload},
+    slideshow,
+    sort::{self, SortKey, SortOrder},
+    state, WallpaperBackend,
+};
 
-const CONFIG_FILE: &str = "~/.config/hyprwall/config.ini";
 const CACHE_SIZE: usize = 100;
+const EFFECTS_PREVIEW_SIZE: i32 = 1280;
+const EFFECTS_PREVIEW_DELAY_MS: u64 = 150;
+const RECENT_SIZE: usize = 10;
+const CONFIG_RELOAD_DELAY_MS: u64 = 200;
+const FOLDER_UPDATE_DELAY_MS: u64 = 500;
+const THUMBNAIL_SIZE: i32 = 250;
+const TEMPORARY_EXTENSIONS: [&str; 4] = ["part", "crdownload", "download", "tmp"];
+
+thread_local! {
+    static RECENT_BOX: RefCell<Option<GtkBox>> = const { RefCell::new(None) };
+    static TAG_COMBO: RefCell<Option<ComboBoxText>> = const { RefCell::new(None) };
+    static CONFIG_MONITOR: RefCell<Option<gio::FileMonitor>> = const { RefCell::new(None) };
+}
 
 struct ImageCache {
     cache: BTreeMap<PathBuf, gdk::Texture>,
     order: VecDeque<PathBuf>,
 }
 
+struct Wallpaper {
+    path: PathBuf,
+    name: String,
+    key: SortKey,
+}
+
 struct ImageLoader {
-    queue: VecDeque<PathBuf>,
     current_folder: Option<PathBuf>,
     cache: Arc<Mutex<ImageCache>>,
-    cancel_flag: Option<Arc<AtomicBool>>,
+    bound: Arc<Mutex<BTreeSet<PathBuf>>>,
+    store: gio::ListStore,
+    filter: CustomFilter,
+    stamps: BTreeMap<PathBuf, FileStamp>,
+    backend_supports_gif: bool,
+    sort: SortOrder,
+    monitor: Option<gio::FileMonitor>,
+    deferred: Option<BTreeSet<PathBuf>>,
+    generation: u64,
 }
 
+type FileStamp = (Option<SystemTime>, u64);
+
 impl ImageCache {
     fn new() -> Self {
         Self {
@@ -64,66 +98,184 @@ impl ImageCache {
         self.order.push_front(path);
     }
 
-    fn get_or_insert(&mut self, path: &Path, max_size: i32) -> Option<Texture> {
-        self.get(path).or_else(|| {
-            let is_gif = path
-                .extension()
-                .and_then(|e| e.to_str())
-                .map_or(false, |ext| ext.eq_ignore_ascii_case("gif"));
+    fn remove(&mut self, path: &Path) {
+        self.cache.remove(path);
+        self.order.retain(|p| p != path);
+    }
+}
 
-            let texture = if is_gif {
-                Texture::from_file(&gio::File::for_path(path)).ok()?
-            } else {
-                let pixbuf = Pixbuf::from_file_at_scale(path, max_size, max_size, true).ok()?;
-                Texture::for_pixbuf(&pixbuf)
-            };
+fn decode_thumbnail(path: &Path, max_size: i32) -> Option<Texture> {
+    if formats::sniff(path) == Some(ImageFormat::Gif) {
+        Texture::from_file(&gio::File::for_path(path)).ok()
+    } else if slideshow::is_slideshow(path) {
+        let slideshow = slideshow::load(path).ok()?;
+        let pixbuf =
+            Pixbuf::from_file_at_scale(slideshow.first_file(), max_size, max_size, true).ok()?;
+        Some(Texture::for_pixbuf(&pixbuf))
+    } else {
+        let pixbuf = Pixbuf::from_file_at_scale(path, max_size, max_size, true).ok()?;
+        Some(Texture::for_pixbuf(&pixbuf))
+    }
+}
 
-            self.insert(path.to_path_buf(), texture.clone());
-            Some(texture)
-        })
+impl Wallpaper {
+    fn new(path: PathBuf, sort: SortOrder) -> Self {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "Unknown".to_string());
+        let key = sort::key(sort, &path);
+        Wallpaper { path, name, key }
+    }
+
+    fn tilde_path(&self) -> String {
+        self.path
+            .to_string_lossy()
+            .replace(&std::env::var("HOME").unwrap_or_default(), "~")
+    }
+
+    fn cmp(&self, other: &Wallpaper) -> std::cmp::Ordering {
+        (&self.key, &self.name).cmp(&(&other.key, &other.name))
     }
 }
 
+fn item_wallpaper(item: &ListItem) -> Option<(PathBuf, String)> {
+    let object = item.item().and_downcast::<BoxedAnyObject>()?;
+    let wallpaper = object.borrow::<Wallpaper>();
+    Some((wallpaper.path.clone(), wallpaper.tilde_path()))
+}
+
 impl ImageLoader {
     fn new() -> Self {
         Self {
-            queue: VecDeque::new(),
             current_folder: None,
             cache: Arc::new(Mutex::new(ImageCache::new())),
-            cancel_flag: None,
+            bound: Arc::new(Mutex::new(BTreeSet::new())),
+            store: gio::ListStore::new::<BoxedAnyObject>(),
+            filter: CustomFilter::new(|_| true),
+            stamps: BTreeMap::new(),
+            backend_supports_gif: false,
+            sort: load_grid_sort(),
+            monitor: None,
+            deferred: None,
+            generation: 0,
         }
     }
 
-    fn load_folder(&mut self, folder: &Path) {
-        if let Some(flag) = self.cancel_flag.as_ref() {
-            flag.store(true, Ordering::Relaxed)
-        }
-        self.queue.clear();
-        self.current_folder = Some(folder.to_path_buf());
-
-        if let Ok(entries) = fs::read_dir(folder) {
-            self.queue.extend(entries.filter_map(|entry| {
-                entry.ok().and_then(|e| {
-                    let path = e.path();
-                    if path.is_file() {
-                        let extension = path
-                            .extension()
-                            .and_then(|e| e.to_str())
-                            .map(|s| s.to_lowercase());
-
-                        match extension.as_deref() {
-                            Some("png" | "jpg" | "jpeg" | "gif") => Some(path),
-                            _ => None,
-                        }
-                    } else {
-                        None
-                    }
-                })
-            }));
-        }
+    fn position(&self, path: &Path) -> Option<u32> {
+        (0..self.store.n_items()).find(|&position| {
+            self.store
+                .item(position)
+                .and_downcast::<BoxedAnyObject>()
+                .is_some_and(|object| object.borrow::<Wallpaper>().path == path)
+        })
+    }
+
+    fn insert(&self, wallpaper: Wallpaper) {
+        self.store
+            .insert_sorted(&BoxedAnyObject::new(wallpaper), compare_wallpapers);
     }
 }
 
+fn compare_wallpapers(a: &glib::Object, b: &glib::Object) -> std::cmp::Ordering {
+    match (
+        a.downcast_ref::<BoxedAnyObject>(),
+        b.downcast_ref::<BoxedAnyObject>(),
+    ) {
+        (Some(a), Some(b)) => a.borrow::<Wallpaper>().cmp(&b.borrow::<Wallpaper>()),
+        _ => std::cmp::Ordering::Equal,
+    }
+}
+
+fn build_grid(image_loader: &Rc<RefCell<ImageLoader>>) -> GridView {
+    let (cache, bound, store, filter) = {
+        let image_loader = image_loader.borrow();
+        (
+            Arc::clone(&image_loader.cache),
+            Arc::clone(&image_loader.bound),
+            image_loader.store.clone(),
+            image_loader.filter.clone(),
+        )
+    };
+
+    let factory = SignalListItemFactory::new();
+    factory.connect_setup(|_, item| {
+        let Some(item) = item.downcast_ref::<ListItem>() else {
+            return;
+        };
+        item.set_child(Some(&build_tile(item)));
+    });
+
+    factory.connect_bind(move |_, item| {
+        let Some(item) = item.downcast_ref::<ListItem>() else {
+            return;
+        };
+        let Some((path, _)) = item_wallpaper(item) else {
+            return;
+        };
+        let Some(button) = item.child().and_downcast::<Button>() else {
+            return;
+        };
+        let Some(image) = button.child().and_downcast::<Image>() else {
+            return;
+        };
+
+        let file_name = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("Unknown");
+        button.set_tooltip_text(Some(file_name));
+
+        let cached = cache.lock().get(&path);
+        image.set_paintable(cached.as_ref());
+        if cached.is_some() {
+            return;
+        }
+
+        bound.lock().insert(path.clone());
+        let cache = Arc::clone(&cache);
+        let bound = Arc::clone(&bound);
+        let item = item.downgrade();
+        glib::spawn_future_local(async move {
+            let loaded_path = path.clone();
+            let texture = gio::spawn_blocking(move || {
+                if !bound.lock().contains(&loaded_path) {
+                    return None;
+                }
+                load_thumbnail(&cache, &loaded_path)
+            })
+            .await
+            .ok()
+            .flatten();
+
+            let Some(item) = item.upgrade() else {
+                return;
+            };
+            if item_wallpaper(&item).map(|(p, _)| p) != Some(path) {
+                return;
+            }
+            if let Some(image) = item
+                .child()
+                .and_downcast::<Button>()
+                .and_then(|button| button.child())
+                .and_downcast::<Image>()
+            {
+                image.set_paintable(texture.as_ref());
+            }
+        });
+    });
+
+    let bound = Arc::clone(&image_loader.borrow().bound);
+    factory.connect_unbind(move |_, item| {
+        if let Some((path, _)) = item.downcast_ref::<ListItem>().and_then(item_wallpaper) {
+            bound.lock().remove(&path);
+        }
+    });
+
+    let model = gtk::FilterListModel::new(Some(store), Some(filter));
+    GridView::new(Some(gtk::NoSelection::new(Some(model))), Some(factory))
+}
+
 pub fn build_ui(app: &Application) {
     let window = ApplicationWindow::builder()
         .application(app)
@@ -140,40 +292,33 @@ pub fn build_ui(app: &Application) {
         .vexpand(true)
         .build();
 
-    let flowbox = FlowBox::builder()
-        .valign(gtk::Align::Start)
-        .halign(gtk::Align::Fill)
-        .selection_mode(gtk::SelectionMode::None)
-        .hexpand(true)
-        .vexpand(true)
-        .homogeneous(true)
-        .row_spacing(10)
-        .column_spacing(10)
-        .build();
-
-    scrolled_window.set_child(Some(&flowbox));
-
-    let flowbox_ref = Rc::new(RefCell::new(flowbox));
     let image_loader = Rc::new(RefCell::new(ImageLoader::new()));
+    scrolled_window.set_child(Some(&build_grid(&image_loader)));
 
     let choose_folder_button = Button::with_label("Change wallpaper folder");
-    let flowbox_clone = Rc::clone(&flowbox_ref);
     let image_loader_clone = Rc::clone(&image_loader);
     let window_weak = window.downgrade();
     choose_folder_button.connect_clicked(move |_| {
         if let Some(window) = window_weak.upgrade() {
-            choose_folder(&window, &flowbox_clone, &image_loader_clone);
+            choose_folder(&window, &image_loader_clone);
         }
     });
 
     let refresh_button = Button::with_label("Refresh");
-    let flowbox_clone = Rc::clone(&flowbox_ref);
     let image_loader_clone = Rc::clone(&image_loader);
     refresh_button.connect_clicked(move |_| {
-        refresh_images(&flowbox_clone, &image_loader_clone);
+        refresh_images(&image_loader_clone);
     });
 
     let random_button = Button::with_label("Random");
+    let previous_button = Button::from_icon_name("go-previous-symbolic");
+    previous_button.set_tooltip_text(Some("Previous wallpaper"));
+    let image_loader_clone = Rc::clone(&image_loader);
+    previous_button.connect_clicked(move |_| step_wallpaper(-1, image_loader_clone.borrow().sort));
+    let next_button = Button::from_icon_name("go-next-symbolic");
+    next_button.set_tooltip_text(Some("Next wallpaper"));
+    let image_loader_clone = Rc::clone(&image_loader);
+    next_button.connect_clicked(move |_| step_wallpaper(1, image_loader_clone.borrow().sort));
     let exit_button = Button::with_label("Exit");
 
     let backend_combo = ComboBoxText::new();
@@ -184,35 +329,39 @@ pub fn build_ui(app: &Application) {
     backend_combo.append(Some("wallutils"), "Wallutils");
     backend_combo.append(Some("feh"), "Feh");
 
-    let current_backend = *crate::CURRENT_BACKEND.lock();
-    let backend_id = match current_backend {
-        WallpaperBackend::None => "none",
-        WallpaperBackend::Hyprpaper => "hyprpaper",
-        WallpaperBackend::Swaybg => "swaybg",
-        WallpaperBackend::Swww => "swww",
-        WallpaperBackend::Wallutils => "wallutils",
-        WallpaperBackend::Feh => "feh",
-    };
+    let backend_id = crate::CURRENT_BACKEND.lock().name();
     backend_combo.set_active_id(Some(backend_id));
 
-    let flowbox_clone_backend = Rc::clone(&flowbox_ref);
     let image_loader_clone_backend = Rc::clone(&image_loader);
     backend_combo.connect_changed(move |combo| {
         if let Some(active_id) = combo.active_id() {
-            let backend = match active_id.as_str() {
-                "none" => WallpaperBackend::None,
-                "hyprpaper" => WallpaperBackend::Hyprpaper,
-                "swaybg" => WallpaperBackend::Swaybg,
-                "swww" => WallpaperBackend::Swww,
-                "wallutils" => WallpaperBackend::Wallutils,
-                "feh" => WallpaperBackend::Feh,
-                _ => return,
+            let Some(backend) = WallpaperBackend::from_name(active_id.as_str()) else {
+                return;
             };
+            if backend == *crate::CURRENT_BACKEND.lock() {
+                return;
+            }
             crate::set_wallpaper_backend(backend);
-            refresh_images(&flowbox_clone_backend, &image_loader_clone_backend);
+            refresh_images(&image_loader_clone_backend);
         }
     });
 
+    let profile_combo = ComboBoxText::new();
+    profile_combo.set_tooltip_text(Some("Profile"));
+    refresh_profiles(&profile_combo);
+
+    let image_loader_clone = Rc::clone(&image_loader);
+    let backend_combo_clone = backend_combo.clone();
+    profile_combo.connect_changed(move |combo| {
+        let Some(name) = combo.active_id() else {
+            return;
+        };
+        if profile::active().as_deref() == Some(name.as_str()) {
+            return;
+        }
+        apply_profile(name.to_string(), &image_loader_clone, &backend_combo_clone);
+    });
+
     let search_button = Button::from_icon_name("system-search-symbolic");
     let search_entry = SearchEntry::new();
     search_entry.set_width_chars(25);
@@ -248,14 +397,55 @@ pub fn build_ui(app: &Application) {
     });
     search_entry.add_controller(key_controller);
 
-    let flowbox_clone = Rc::clone(&flowbox_ref);
+    let tag_combo = ComboBoxText::new();
+    tag_combo.set_tooltip_text(Some("Filter by tag"));
+
+    let image_loader_clone = Rc::clone(&image_loader);
+    let tag_combo_clone = tag_combo.clone();
     search_entry.connect_changed(move |entry| {
-        filter_wallpapers(&flowbox_clone, entry.text());
+        filter_wallpapers(
+            &image_loader_clone,
+            entry.text(),
+            tag_combo_clone.active_id(),
+        );
+    });
+
+    let image_loader_clone = Rc::clone(&image_loader);
+    let search_entry_clone = search_entry.clone();
+    tag_combo.connect_changed(move |combo| {
+        filter_wallpapers(
+            &image_loader_clone,
+            search_entry_clone.text(),
+            combo.active_id(),
+        );
+    });
+
+    TAG_COMBO.with(|cell| *cell.borrow_mut() = Some(tag_combo.clone()));
+    refresh_tag_filter();
+
+    let sort_combo = ComboBoxText::new();
+    sort_combo.set_tooltip_text(Some("Sort by"));
+    for order in SortOrder::ALL {
+        sort_combo.append(Some(order.name()), order.label());
+    }
+    sort_combo.set_active_id(Some(image_loader.borrow().sort.name()));
+
+    let image_loader_clone = Rc::clone(&image_loader);
+    sort_combo.connect_changed(move |combo| {
+        let Some(order) = combo
+            .active_id()
+            .and_then(|id| SortOrder::from_name(id.as_str()))
+        else {
+            return;
+        };
+        sort_images(order, &image_loader_clone);
     });
 
     let left_box = GtkBox::new(gtk::Orientation::Horizontal, 5);
     left_box.set_halign(gtk::Align::Start);
     left_box.append(&search_button);
+    left_box.append(&tag_combo);
+    left_box.append(&sort_combo);
 
     let bottom_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
     bottom_box.set_margin_top(10);
@@ -269,37 +459,58 @@ pub fn build_ui(app: &Application) {
     right_box.set_hexpand(true);
     right_box.append(&choose_folder_button);
     right_box.append(&refresh_button);
+    right_box.append(&previous_button);
     right_box.append(&random_button);
+    right_box.append(&next_button);
     right_box.append(&backend_combo);
+    right_box.append(&profile_combo);
     right_box.append(&exit_button);
 
     bottom_box.append(&left_box);
     bottom_box.append(&right_box);
 
+    let recent_box = GtkBox::new(gtk::Orientation::Horizontal, 5);
+    let recent_label = gtk::Label::new(Some("Recent"));
+    recent_label.set_margin_end(5);
+
+    let recent_scrolled_window = ScrolledWindow::builder()
+        .hscrollbar_policy(gtk::PolicyType::Automatic)
+        .vscrollbar_policy(gtk::PolicyType::Never)
+        .hexpand(true)
+        .child(&recent_box)
+        .build();
+
+    let recent_strip = GtkBox::new(gtk::Orientation::Horizontal, 5);
+    recent_strip.set_margin_top(10);
+    recent_strip.set_margin_start(10);
+    recent_strip.set_margin_end(10);
+    recent_strip.append(&recent_label);
+    recent_strip.append(&recent_scrolled_window);
+
+    RECENT_BOX.with(|cell| *cell.borrow_mut() = Some(recent_box));
+    refresh_recent();
+
     let main_box = GtkBox::new(gtk::Orientation::Vertical, 0);
     main_box.append(&scrolled_window);
+    main_box.append(&recent_strip);
     main_box.append(&bottom_box);
 
     window.set_child(Some(&main_box));
 
-    let flowbox_clone_window = Rc::clone(&flowbox_ref);
+    watch_config(&image_loader, &backend_combo, &profile_combo, &sort_combo);
+
     let image_loader_clone_window = Rc::clone(&image_loader);
     window.connect_show(move |_| {
         if let Some(last_path) = load_last_path() {
-            let flowbox_clone2 = Rc::clone(&flowbox_clone_window);
             let image_loader_clone2 = Rc::clone(&image_loader_clone_window);
             glib::idle_add_local(move || {
-                load_images(&last_path, &flowbox_clone2, &image_loader_clone2);
+                load_images(&last_path, &image_loader_clone2);
                 glib::ControlFlow::Break
             });
         }
     });
 
-    let flowbox_clone_random = Rc::clone(&flowbox_ref);
-    let image_loader_clone_random = Rc::clone(&image_loader);
-    random_button.connect_clicked(move |_| {
-        set_random_wallpaper(&flowbox_clone_random, &image_loader_clone_random);
-    });
+    random_button.connect_clicked(|_| set_random_wallpaper());
 
     let app_clone = app.clone();
     exit_button.connect_clicked(move |_| {
@@ -309,11 +520,7 @@ pub fn build_ui(app: &Application) {
     window.present();
 }
 
-fn choose_folder(
-    window: &ApplicationWindow,
-    flowbox: &Rc<RefCell<FlowBox>>,
-    image_loader: &Rc<RefCell<ImageLoader>>,
-) {
+fn choose_folder(window: &ApplicationWindow, image_loader: &Rc<RefCell<ImageLoader>>) {
     let dialog = gtk::FileChooserDialog::new(
         Some("Change wallpaper folder"),
         Some(window),
@@ -328,12 +535,11 @@ fn choose_folder(
         let _ = dialog.set_current_folder(Some(&gio::File::for_path(last_path)));
     }
 
-    let flowbox_clone = Rc::clone(flowbox);
     let image_loader_clone = Rc::clone(image_loader);
     dialog.connect_response(move |dialog, response| {
         if response == gtk::ResponseType::Accept {
             if let Some(folder) = dialog.file().and_then(|f| f.path()) {
-                load_images(&folder, &flowbox_clone, &image_loader_clone);
+                load_images(&folder, &image_loader_clone);
                 save_last_path(&folder);
             }
         }
@@ -343,217 +549,552 @@ fn choose_folder(
     dialog.show();
 }
 
-fn load_images(
-    folder: &Path,
-    flowbox: &Rc<RefCell<FlowBox>>,
-    image_loader: &Rc<RefCell<ImageLoader>>,
-) {
-    let mut image_loader = image_loader.borrow_mut();
+fn file_stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = fs::metadata(path)
+        .ok()
+        .filter(|metadata| metadata.is_file())?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
+
+fn folder_stamps(folder: &Path) -> BTreeMap<PathBuf, FileStamp> {
+    fs::read_dir(folder)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter_map(|path| file_stamp(&path).map(|stamp| (path, stamp)))
+                .collect()
+        })
+        .unwrap_or_default()
+}
 
-    if let Some(flag) = &image_loader.cancel_flag {
-        flag.store(true, Ordering::Relaxed);
+fn is_temporary(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.starts_with('.'));
+    let partial = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| TEMPORARY_EXTENSIONS.contains(&extension.to_lowercase().as_str()));
+    hidden || partial
+}
+
+fn is_shown(path: &Path, backend_supports_gif: bool) -> bool {
+    if is_temporary(path) || !formats::is_wallpaper(path) {
+        return false;
+    }
+    backend_supports_gif || formats::sniff(path) != Some(ImageFormat::Gif)
+}
+
+fn load_thumbnail(cache: &Mutex<ImageCache>, path: &Path) -> Option<Texture> {
+    if let Some(texture) = cache.lock().get(path) {
+        return Some(texture);
     }
 
-    image_loader.load_folder(folder);
+    let texture = decode_thumbnail(path, THUMBNAIL_SIZE);
+    match &texture {
+        Some(texture) => cache.lock().insert(path.to_path_buf(), texture.clone()),
+        None => eprintln!("Failed to load texture for {:?}", path),
+    }
+    texture
+}
 
-    let batch = image_loader.queue.drain(..).collect::<Vec<_>>();
-    let cache = Arc::clone(&image_loader.cache);
+fn build_tile(item: &ListItem) -> Button {
+    let image = Image::new();
+    image.set_pixel_size(THUMBNAIL_SIZE);
+
+    let button = Button::builder().child(&image).build();
+    button.set_has_frame(false);
+    button.set_margin_top(5);
+    button.set_margin_bottom(5);
+    button.set_margin_start(5);
+    button.set_margin_end(5);
+
+    let motion_controller = EventControllerMotion::new();
+    let button_weak = button.downgrade();
+    motion_controller.connect_enter(move |_, _, _| {
+        if let Some(button) = button_weak.upgrade() {
+            button.set_has_frame(true);
+        }
+    });
+    let button_weak = button.downgrade();
+    motion_controller.connect_leave(move |_| {
+        if let Some(button) = button_weak.upgrade() {
+            button.set_has_frame(false);
+        }
+    });
+    button.add_controller(motion_controller);
+
+    let gesture = gtk::GestureClick::new();
+    gesture.set_button(3);
+    let item_weak = item.downgrade();
+    let button_weak = button.downgrade();
+    gesture.connect_released(move |_, _, _, _| {
+        let Some((_, path)) = item_weak.upgrade().as_ref().and_then(item_wallpaper) else {
+            return;
+        };
+        if let Some(button) = button_weak.upgrade() {
+            show_context_menu(&path, &button);
+        }
+    });
+    button.add_controller(gesture);
+
+    let item_weak = item.downgrade();
+    button.connect_clicked(move |_| {
+        if let Some((_, path)) = item_weak.upgrade().as_ref().and_then(item_wallpaper) {
+            crate::set_wallpaper(path);
+        }
+    });
+
+    button
+}
+
+fn load_images(folder: &Path, image_loader: &Rc<RefCell<ImageLoader>>) {
     let backend_supports_gif = load_wallpaper_backend() == Some(WallpaperBackend::Swww);
+    let (generation, sort) = {
+        let mut loader = image_loader.borrow_mut();
+        loader.generation += 1;
+        if loader.current_folder.as_deref() != Some(folder) {
+            loader.store.remove_all();
+        }
+        loader.current_folder = Some(folder.to_path_buf());
+        loader.backend_supports_gif = backend_supports_gif;
+        loader.stamps.clear();
+        loader.deferred = Some(BTreeSet::new());
 
-    let flowbox_clone = Rc::clone(flowbox);
-    let (sender, receiver) = unbounded::<(Texture, String)>();
+        if let Some(monitor) = loader.monitor.take() {
+            monitor.cancel();
+        }
+        loader.monitor = watch_folder(folder, image_loader);
+        (loader.generation, loader.sort)
+    };
 
-    while let Some(child) = flowbox.borrow().first_child() {
-        flowbox.borrow().remove(&child);
-    }
+    let folder = folder.to_path_buf();
+    let image_loader = Rc::clone(image_loader);
+    glib::spawn_future_local(async move {
+        let Ok((stamps, mut wallpapers)) = gio::spawn_blocking(move || {
+            let stamps = folder_stamps(&folder);
+            let wallpapers: Vec<Wallpaper> = stamps
+                .par_iter()
+                .filter(|(path, _)| is_shown(path, backend_supports_gif))
+                .map(|(path, _)| Wallpaper::new(path.clone(), sort))
+                .collect();
+            (stamps, wallpapers)
+        })
+        .await
+        else {
+            return;
+        };
+
+        let deferred = {
+            let mut loader = image_loader.borrow_mut();
+            if loader.generation != generation {
+                return;
+            }
 
-    let cancel_flag = Arc::new(AtomicBool::new(false));
-    let cancel_flag_clone = Arc::clone(&cancel_flag);
-    let cancel_flag_clone2 = Arc::clone(&cancel_flag);
-
-    std::thread::spawn(move || {
-        let num_cores = num_cpus::get();
-        batch
-            .par_iter()
-            .with_max_len(num_cores)
-            .for_each_with(sender.clone(), |s, path| {
-                if cancel_flag_clone.load(Ordering::Relaxed) {
-                    return;
-                }
+            wallpapers.sort_by(Wallpaper::cmp);
+            let objects: Vec<BoxedAnyObject> =
+                wallpapers.into_iter().map(BoxedAnyObject::new).collect();
+            loader.store.splice(0, loader.store.n_items(), &objects);
+            loader.stamps = stamps;
+            loader.deferred.take().unwrap_or_default()
+        };
+
+        if !deferred.is_empty() {
+            update_tiles(deferred, &image_loader);
+        }
+    });
+}
 
-                let is_gif = path
-                    .extension()
-                    .and_then(|e| e.to_str())
-                    .map_or(false, |ext| ext.eq_ignore_ascii_case("gif"));
+fn watch_folder(
+    folder: &Path,
+    image_loader: &Rc<RefCell<ImageLoader>>,
+) -> Option<gio::FileMonitor> {
+    let monitor = gio::File::for_path(folder)
+        .monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
+        .map_err(|e| eprintln!("Failed to watch {}: {}", folder.display(), e))
+        .ok()?;
+
+    let folder = folder.to_path_buf();
+    let pending = Rc::new(RefCell::new(BTreeSet::new()));
+    let image_loader = Rc::downgrade(image_loader);
+
+    monitor.connect_changed(move |_, file, other_file, event| {
+        if matches!(
+            event,
+            gio::FileMonitorEvent::Changed | gio::FileMonitorEvent::AttributeChanged
+        ) {
+            return;
+        }
 
-                if is_gif && !backend_supports_gif {
-                    return;
-                }
+        let paths: Vec<PathBuf> = [Some(file), other_file]
+            .into_iter()
+            .flatten()
+            .filter_map(|file| file.path())
+            .filter(|path| path.parent() == Some(folder.as_path()))
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
 
-                let texture = {
-                    let mut cache = cache.lock();
-                    match cache.get_or_insert(path, 250) {
-                        Some(texture) => texture,
-                        None => {
-                            eprintln!("Failed to load texture for {:?}", path);
-                            return;
-                        }
-                    }
-                };
+        let scheduled = !pending.borrow().is_empty();
+        pending.borrow_mut().extend(paths);
+        if scheduled {
+            return;
+        }
 
-                let path_clone = path.to_str().unwrap_or("").to_string();
-                let path_with_tilde =
-                    path_clone.replace(&std::env::var("HOME").unwrap_or_default(), "~");
-                if s.send((texture, path_with_tilde)).is_err() {
-                    cancel_flag_clone.store(true, Ordering::Relaxed);
+        let pending = Rc::clone(&pending);
+        let image_loader = image_loader.clone();
+        glib::timeout_add_local_once(
+            std::time::Duration::from_millis(FOLDER_UPDATE_DELAY_MS),
+            move || {
+                let paths = std::mem::take(&mut *pending.borrow_mut());
+                if let Some(image_loader) = image_loader.upgrade() {
+                    update_tiles(paths, &image_loader);
                 }
-            });
+            },
+        );
     });
 
-    glib::source::idle_add_local(move || {
-        if cancel_flag_clone2.load(Ordering::Relaxed) {
-            return ControlFlow::Break;
-        }
+    Some(monitor)
+}
 
-        let flowbox = flowbox_clone.borrow_mut();
-        for _ in 0..10 {
-            match receiver.try_recv() {
-                Ok((texture, path_clone)) => {
-                    let image = Image::from_paintable(Some(&texture));
-                    image.set_pixel_size(250);
+fn update_tiles(paths: BTreeSet<PathBuf>, image_loader: &Rc<RefCell<ImageLoader>>) {
+    let (changed, removed): (Vec<PathBuf>, Vec<PathBuf>) =
+        paths.into_iter().partition(|path| path.is_file());
 
-                    let button = Button::builder().child(&image).build();
-                    button.set_has_frame(false);
+    let (generation, backend_supports_gif, sort) = {
+        let mut loader = image_loader.borrow_mut();
+        if let Some(deferred) = loader.deferred.as_mut() {
+            deferred.extend(changed.into_iter().chain(removed));
+            return;
+        }
 
-                    let motion_controller = EventControllerMotion::new();
-                    let button_weak = button.downgrade();
-                    motion_controller.connect_enter(move |_, _, _| {
-                        if let Some(button) = button_weak.upgrade() {
-                            button.set_has_frame(true);
-                        }
-                    });
-                    let button_weak = button.downgrade();
-                    motion_controller.connect_leave(move |_| {
-                        if let Some(button) = button_weak.upgrade() {
-                            button.set_has_frame(false);
-                        }
-                    });
-                    button.add_controller(motion_controller);
-
-                    let gesture = gtk::GestureClick::new();
-                    gesture.set_button(3);
-                    let path_clone_preview = path_clone.clone();
-                    gesture.connect_released(move |gesture, _, _, _| {
-                        if let Some(widget) = gesture.widget() {
-                            show_preview_window(&path_clone_preview, &widget);
-                        }
-                    });
-                    button.add_controller(gesture);
+        for path in &removed {
+            loader.stamps.remove(path);
+            if let Some(position) = loader.position(path) {
+                loader.store.remove(position);
+            }
+        }
+        for path in &changed {
+            if let Some(stamp) = file_stamp(path) {
+                loader.stamps.insert(path.clone(), stamp);
+            }
+            loader.cache.lock().remove(path);
+        }
+        (loader.generation, loader.backend_supports_gif, loader.sort)
+    };
 
-                    let file_name = Path::new(&path_clone)
-                        .file_name()
-                        .and_then(|name| name.to_str())
-                        .unwrap_or("Unknown");
-                    button.set_tooltip_text(Some(file_name));
+    if changed.is_empty() {
+        return;
+    }
 
-                    let path_clone2 = path_clone.clone();
-                    button.connect_clicked(move |_| {
-                        crate::set_wallpaper(path_clone2.clone());
-                    });
+    let image_loader = Rc::clone(image_loader);
+    glib::spawn_future_local(async move {
+        let Ok(loaded) = gio::spawn_blocking(move || {
+            changed
+                .into_iter()
+                .map(|path| {
+                    let wallpaper = is_shown(&path, backend_supports_gif)
+                        .then(|| Wallpaper::new(path.clone(), sort));
+                    (path, wallpaper)
+                })
+                .collect::<Vec<_>>()
+        })
+        .await
+        else {
+            return;
+        };
+
+        let loader = image_loader.borrow();
+        if loader.generation != generation {
+            return;
+        }
 
-                    flowbox.insert(&button, -1);
-                }
-                Err(crossbeam_channel::TryRecvError::Empty) => break,
-                Err(crossbeam_channel::TryRecvError::Disconnected) => {
-                    cancel_flag_clone2.store(true, Ordering::Relaxed);
-                    return ControlFlow::Break;
-                }
+        for (path, wallpaper) in loaded {
+            if let Some(position) = loader.position(&path) {
+                loader.store.remove(position);
+            }
+            if let Some(wallpaper) = wallpaper {
+                loader.insert(wallpaper);
             }
         }
-        ControlFlow::Continue
     });
-
-    image_loader.cancel_flag = Some(cancel_flag);
 }
 
-fn load_last_path() -> Option<PathBuf> {
-    let config_path = shellexpand::tilde("~/.config/hyprwall/config.ini").into_owned();
-    fs::File::open(config_path).ok().and_then(|mut file| {
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).ok()?;
-        contents
-            .lines()
-            .find(|line| line.starts_with("folder = "))
-            .map(|line| {
-                PathBuf::from(shellexpand::tilde(line.trim_start_matches("folder = ")).into_owned())
-            })
-    })
+pub fn load_last_path() -> Option<PathBuf> {
+    let configured = config::get("folder");
+    let opened = state::get("last_folder").filter(|_| {
+        state::get("last_folder_config") == Some(configured.clone().unwrap_or_default())
+    });
+
+    opened
+        .or(configured)
+        .filter(|folder| folder != "none")
+        .map(|folder| PathBuf::from(shellexpand::tilde(&folder).into_owned()))
 }
 
 pub fn save_last_path(path: &Path) {
-    let config_path = shellexpand::tilde(CONFIG_FILE).into_owned();
-    let mut contents = String::new();
-
-    if let Ok(mut file) = fs::File::open(&config_path) {
-        let _ = file.read_to_string(&mut contents);
-    }
-
     let path_with_tilde = path
         .to_string_lossy()
         .replace(&std::env::var("HOME").unwrap_or_default(), "~");
+    let configured = config::get("folder").unwrap_or_default();
+    state::set_all(&[
+        ("last_folder", &path_with_tilde),
+        ("last_folder_config", &configured),
+    ]);
+}
 
-    if let Some(pos) = contents.find("folder = ") {
-        let end_pos = contents[pos..]
-            .find('\n')
-            .map(|p| p + pos)
-            .unwrap_or(contents.len());
-        contents.replace_range(pos..end_pos, &format!("folder = {}", path_with_tilde));
-    } else {
-        contents.push_str(&format!("folder = {}\n", path_with_tilde));
+fn set_random_wallpaper() {
+    let filter = TAG_COMBO.with(|cell| {
+        cell.borrow()
+            .as_ref()
+            .and_then(|combo| combo.active_id())
+            .map(|id| id.to_string())
+    });
+
+    glib::spawn_future_local(async move {
+        let result = match filter.as_deref() {
+            Some("favorites") => crate::get_random_favorite().await,
+            Some(id) => crate::get_random_wallpaper(id.strip_prefix("tag:")).await,
+            None => crate::get_random_wallpaper(None).await,
+        };
+        match result {
+            Ok(path) => crate::set_wallpaper(path),
+            Err(e) => custom_error_popup("Error getting random wallpaper", &e, true),
+        }
+    });
+}
+
+fn refresh_profiles(combo: &ComboBoxText) {
+    combo.remove_all();
+    let profiles = profile::names();
+    for name in &profiles {
+        combo.append(Some(name), name);
     }
+    if let Some(active) = profile::active() {
+        combo.set_active_id(Some(&active));
+    }
+    combo.set_visible(!profiles.is_empty());
+}
+
+fn watch_config(
+    image_loader: &Rc<RefCell<ImageLoader>>,
+    backend_combo: &ComboBoxText,
+    profile_combo: &ComboBoxText,
+    sort_combo: &ComboBoxText,
+) {
+    let config_path = PathBuf::from(config::config_path());
+    let (Some(dir), Some(name)) = (config_path.parent(), config_path.file_name()) else {
+        return;
+    };
+    let name = name.to_os_string();
 
-    if let Ok(mut file) = fs::OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(&config_path)
+    let monitor = match gio::File::for_path(dir)
+        .monitor_directory(gio::FileMonitorFlags::WATCH_MOVES, gio::Cancellable::NONE)
     {
-        let _ = file.write_all(contents.as_bytes());
+        Ok(monitor) => monitor,
+        Err(e) => {
+            eprintln!("Failed to watch {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    let watcher = Rc::new(RefCell::new(ConfigWatcher::new()));
+    let pending = Rc::new(Cell::new(false));
+    let image_loader = Rc::clone(image_loader);
+    let backend_combo = backend_combo.clone();
+    let profile_combo = profile_combo.clone();
+    let sort_combo = sort_combo.clone();
+
+    monitor.connect_changed(move |_, file, other_file, _| {
+        let touched = [Some(file), other_file]
+            .into_iter()
+            .flatten()
+            .any(|file| file.basename().is_some_and(|base| base == name));
+        if !touched || pending.get() {
+            return;
+        }
+
+        pending.set(true);
+        let pending = Rc::clone(&pending);
+        let watcher = Rc::clone(&watcher);
+        let image_loader = Rc::clone(&image_loader);
+        let backend_combo = backend_combo.clone();
+        let profile_combo = profile_combo.clone();
+        let sort_combo = sort_combo.clone();
+        glib::timeout_add_local_once(
+            std::time::Duration::from_millis(CONFIG_RELOAD_DELAY_MS),
+            move || {
+                pending.set(false);
+                let reload = watcher.borrow_mut().reload();
+                apply_config(
+                    &reload,
+                    &image_loader,
+                    &backend_combo,
+                    &profile_combo,
+                    &sort_combo,
+                );
+            },
+        );
+    });
+
+    CONFIG_MONITOR.with(|cell| *cell.borrow_mut() = Some(monitor));
+}
+
+fn apply_config(
+    reload: &Reload,
+    image_loader: &Rc<RefCell<ImageLoader>>,
+    backend_combo: &ComboBoxText,
+    profile_combo: &ComboBoxText,
+    sort_combo: &ComboBoxText,
+) {
+    if !reload.errors.is_empty() {
+        custom_error_popup(
+            "Invalid config",
+            &format!("These settings were ignored:\n{}", reload.errors.join("\n")),
+            false,
+        );
+    }
+
+    refresh_profiles(profile_combo);
+
+    if let Some(order) = reload.value("sort").and_then(SortOrder::from_name) {
+        sort_combo.set_active_id(Some(order.name()));
+    }
+
+    let backend_changed = reload.apply_backend();
+    if backend_changed {
+        let backend = *crate::CURRENT_BACKEND.lock();
+        backend_combo.set_active_id(Some(backend.name()));
+    }
+
+    let folder = reload
+        .value("folder")
+        .map(|folder| PathBuf::from(shellexpand::tilde(folder).into_owned()))
+        .filter(|folder| image_loader.borrow().current_folder.as_ref() != Some(folder));
+    if let Some(folder) = folder {
+        save_last_path(&folder);
+        load_images(&folder, image_loader);
+    } else if backend_changed {
+        refresh_images(image_loader);
     }
 }
 
-fn set_random_wallpaper(_flowbox: &Rc<RefCell<FlowBox>>, image_loader: &Rc<RefCell<ImageLoader>>) {
-    let image_loader = image_loader.borrow();
-    if let Some(current_folder) = &image_loader.current_folder {
-        if let Ok(entries) = fs::read_dir(current_folder) {
-            let images: Vec<_> = entries
-                .filter_map(|entry| {
-                    entry.ok().and_then(|e| {
-                        let path = e.path();
-                        if path.is_file()
-                            && matches!(
-                                path.extension().and_then(|e| e.to_str()),
-                                Some("png" | "jpg" | "jpeg")
-                            )
-                        {
-                            Some(
-                                path.to_string_lossy()
-                                    .into_owned()
-                                    .replace(&std::env::var("HOME").unwrap_or_default(), "~"),
-                            )
-                        } else {
-                            None
-                        }
-                    })
-                })
-                .collect();
+fn load_grid_sort() -> SortOrder {
+    let configured = config::get("sort").unwrap_or_default();
+    state::get("grid_sort")
+        .filter(|_| state::get("grid_sort_config") == Some(configured))
+        .and_then(|sort| SortOrder::from_name(&sort))
+        .unwrap_or_else(SortOrder::load)
+}
 
-            if let Some(random_image) = images.choose(&mut rand::thread_rng()) {
-                crate::set_wallpaper(random_image.to_string());
-            }
+fn sort_images(order: SortOrder, image_loader: &Rc<RefCell<ImageLoader>>) {
+    let folder = {
+        let mut loader = image_loader.borrow_mut();
+        if loader.sort == order {
+            return;
         }
+        loader.sort = order;
+        loader.current_folder.clone()
+    };
+
+    let configured = config::get("sort").unwrap_or_default();
+    state::set_all(&[
+        ("grid_sort", order.name()),
+        ("grid_sort_config", &configured),
+    ]);
+    if let Some(folder) = folder {
+        load_images(&folder, image_loader);
     }
 }
 
+fn apply_profile(
+    name: String,
+    image_loader: &Rc<RefCell<ImageLoader>>,
+    backend_combo: &ComboBoxText,
+) {
+    let image_loader = Rc::clone(image_loader);
+    let backend_combo = backend_combo.clone();
+    glib::spawn_future_local(async move {
+        if let Err(e) = profile::apply(&name).await {
+            custom_error_popup("Error applying profile", &e, true);
+        }
+
+        let backend = *crate::CURRENT_BACKEND.lock();
+        backend_combo.set_active_id(Some(backend.name()));
+        if let Some(folder) = load_last_path() {
+            load_images(&folder, &image_loader);
+        }
+        refresh_recent();
+    });
+}
+
+fn step_wallpaper(step: isize, order: SortOrder) {
+    glib::spawn_future_local(async move {
+        match crate::get_step_wallpaper(step, order).await {
+            Ok(path) => {
+                crate::save_cursor(&path);
+                crate::set_wallpaper(path);
+            }
+            Err(e) => custom_error_popup("Error getting wallpaper", &e, true),
+        }
+    });
+}
+
+pub fn refresh_recent() {
+    RECENT_BOX.with(|cell| {
+        let Some(recent_box) = cell.borrow().clone() else {
+            return;
+        };
+
+        while let Some(child) = recent_box.first_child() {
+            recent_box.remove(&child);
+        }
+
+        for path in history::recent(RECENT_SIZE) {
+            let expanded = shellexpand::tilde(&path).into_owned();
+            let image = Image::new();
+            image.set_pixel_size(64);
+
+            let button = Button::builder().child(&image).build();
+            button.set_has_frame(false);
+            button.set_tooltip_text(
+                Path::new(&expanded)
+                    .file_name()
+                    .and_then(|name| name.to_str()),
+            );
+            button.connect_clicked(move |_| {
+                crate::set_wallpaper(path.clone());
+            });
+
+            recent_box.append(&button);
+
+            let recent_box = recent_box.clone();
+            glib::spawn_future_local(async move {
+                let texture = gio::spawn_blocking(move || {
+                    let thumbnail =
+                        slideshow::resolve(&expanded).unwrap_or_else(|_| expanded.clone());
+                    Pixbuf::from_file_at_scale(&thumbnail, 64, 64, true)
+                        .ok()
+                        .map(|pixbuf| Texture::for_pixbuf(&pixbuf))
+                })
+                .await
+                .ok()
+                .flatten();
+
+                if button.parent().as_ref() != Some(recent_box.upcast_ref()) {
+                    return;
+                }
+                match texture {
+                    Some(texture) => image.set_paintable(Some(&texture)),
+                    None => recent_box.remove(&button),
+                }
+            });
+        }
+    });
+}
+
 pub fn custom_error_popup(title: &str, text: &str, modal: bool) {
     let dialog = MessageDialog::builder()
         .message_type(gtk::MessageType::Error)
@@ -571,143 +1112,371 @@ pub fn custom_error_popup(title: &str, text: &str, modal: bool) {
 }
 
 pub fn load_last_wallpaper() -> Option<String> {
-    let config_path = shellexpand::tilde(CONFIG_FILE).into_owned();
-    fs::File::open(config_path).ok().and_then(|mut file| {
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).ok()?;
-        contents
-            .lines()
-            .find(|line| line.starts_with("last_wallpaper = "))
-            .map(|line| line.trim_start_matches("last_wallpaper = ").to_string())
-    })
+    state::get("last_wallpaper").filter(|wallpaper| wallpaper != "none")
 }
 
 pub fn save_last_wallpaper(path: &str) {
-    let config_path = shellexpand::tilde(CONFIG_FILE).into_owned();
-    let mut contents = String::new();
+    state::set_all(&[("last_wallpaper", path), ("last_monitors", "")]);
+}
+
+pub fn load_last_monitor_wallpapers() -> Vec<(String, String)> {
+    state::get("last_monitors")
+        .and_then(|monitors| serde_json::from_str(&monitors).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_last_monitor_wallpapers(wallpapers: &[(String, String)]) {
+    let monitors = serde_json::to_string(wallpapers).unwrap_or_default();
+    state::set_all(&[
+        ("last_wallpaper", &wallpapers[0].1),
+        ("last_monitors", &monitors),
+    ]);
+}
 
-    if let Ok(mut file) = fs::File::open(&config_path) {
-        let _ = file.read_to_string(&mut contents);
+pub fn save_wallpaper_backend(backend: &WallpaperBackend) {
+    if load_wallpaper_backend() != Some(*backend) {
+        config::set("backend", backend.name());
     }
+}
 
-    let mut lines: Vec<String> = contents.lines().map(String::from).collect();
-    let wallpaper_line = format!("last_wallpaper = {}", path);
+pub fn load_wallpaper_backend() -> Option<WallpaperBackend> {
+    config::get("backend").and_then(|backend| WallpaperBackend::from_name(&backend))
+}
 
-    if let Some(pos) = lines
-        .iter()
-        .position(|line| line.starts_with("last_wallpaper = "))
-    {
-        lines[pos] = wallpaper_line;
-    } else {
-        lines.push(wallpaper_line);
+fn refresh_images(image_loader: &Rc<RefCell<ImageLoader>>) {
+    let (current_folder, loaded_with_gif) = {
+        let image_loader = image_loader.borrow();
+        (
+            image_loader.current_folder.clone(),
+            image_loader.backend_supports_gif,
+        )
+    };
+    let Some(folder) = current_folder else {
+        return;
+    };
+
+    let backend_supports_gif = load_wallpaper_backend() == Some(WallpaperBackend::Swww);
+    if backend_supports_gif != loaded_with_gif {
+        load_images(&folder, image_loader);
+        return;
     }
 
-    let new_contents = lines.join("\n");
+    let stamps = folder_stamps(&folder);
+    let paths: BTreeSet<PathBuf> = {
+        let image_loader = image_loader.borrow();
+        stamps
+            .iter()
+            .filter(|(path, stamp)| image_loader.stamps.get(*path) != Some(stamp))
+            .map(|(path, _)| path.clone())
+            .chain(
+                image_loader
+                    .stamps
+                    .keys()
+                    .filter(|path| !stamps.contains_key(*path))
+                    .cloned(),
+            )
+            .collect()
+    };
 
-    if let Ok(mut file) = fs::OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(&config_path)
-    {
-        let _ = writeln!(file, "{}", new_contents);
+    if !paths.is_empty() {
+        update_tiles(paths, image_loader);
     }
 }
 
-pub fn save_wallpaper_backend(backend: &WallpaperBackend) {
-    let config_path = shellexpand::tilde(CONFIG_FILE).into_owned();
-    let mut contents = String::new();
+pub fn refresh_tag_filter() {
+    TAG_COMBO.with(|cell| {
+        let Some(tag_combo) = cell.borrow().clone() else {
+            return;
+        };
+
+        let active_id = tag_combo.active_id();
+        tag_combo.remove_all();
+        tag_combo.append(Some("all"), "All");
+        tag_combo.append(Some("favorites"), "Favorites");
+        for tag in library::load().all_tags() {
+            tag_combo.append(Some(&format!("tag:{}", tag)), &tag);
+        }
 
-    if let Ok(mut file) = fs::File::open(&config_path) {
-        let _ = file.read_to_string(&mut contents);
-    }
+        if !active_id.is_some_and(|id| tag_combo.set_active_id(Some(&id))) {
+            tag_combo.set_active_id(Some("all"));
+        }
+    });
+}
 
-    let backend_str = match backend {
-        WallpaperBackend::Hyprpaper => "hyprpaper",
-        WallpaperBackend::Swaybg => "swaybg",
-        WallpaperBackend::Swww => "swww",
-        WallpaperBackend::Wallutils => "wallutils",
-        WallpaperBackend::Feh => "feh",
-        WallpaperBackend::None => "none",
-    };
+fn filter_wallpapers(
+    image_loader: &Rc<RefCell<ImageLoader>>,
+    search_text: impl AsRef<str>,
+    tag_filter: Option<impl AsRef<str>>,
+) {
+    let search_text = search_text.as_ref().to_lowercase();
+    let tag_filter = tag_filter
+        .map(|t| t.as_ref().to_string())
+        .filter(|t| t != "all");
+    let library = tag_filter.as_ref().map(|_| library::load());
 
-    let mut lines: Vec<String> = contents.lines().map(String::from).collect();
-    let backend_line = format!("backend = {}", backend_str);
+    image_loader.borrow().filter.set_filter_func(move |object| {
+        let Some(object) = object.downcast_ref::<BoxedAnyObject>() else {
+            return true;
+        };
+        let wallpaper = object.borrow::<Wallpaper>();
 
-    if let Some(pos) = lines.iter().position(|line| line.starts_with("backend = ")) {
-        lines[pos] = backend_line;
+        if !wallpaper.name.to_lowercase().contains(&search_text) {
+            return false;
+        }
+
+        match (&tag_filter, &library) {
+            (Some(tag_filter), Some(library)) => {
+                let path = wallpaper.tilde_path();
+                match tag_filter.strip_prefix("tag:") {
+                    Some(tag) => library.has_tag(&path, tag),
+                    None => library.is_favorite(&path),
+                }
+            }
+            _ => true,
+        }
+    });
+}
+
+fn show_context_menu(path: &str, button: &Button) {
+    let popover = gtk::Popover::new();
+    popover.set_parent(button);
+    popover.connect_closed(|popover| {
+        popover.unparent();
+    });
+
+    let menu_box = GtkBox::new(gtk::Orientation::Vertical, 5);
+    let library = library::load();
+
+    let preview_button = Button::with_label("Preview");
+    preview_button.set_has_frame(false);
+    let path_clone = path.to_string();
+    let popover_clone = popover.clone();
+    let button_weak = button.downgrade();
+    preview_button.connect_clicked(move |_| {
+        popover_clone.popdown();
+        if let Some(button) = button_weak.upgrade() {
+            show_preview_window(&path_clone, &button);
+        }
+    });
+
+    let favorite_button = Button::with_label(if library.is_favorite(path) {
+        "Remove from favorites"
     } else {
-        lines.push(backend_line);
-    }
+        "Add to favorites"
+    });
+    favorite_button.set_has_frame(false);
+    let path_clone = path.to_string();
+    let popover_clone = popover.clone();
+    favorite_button.connect_clicked(move |_| {
+        popover_clone.popdown();
+        library::update(|library| library.toggle_favorite(&path_clone));
+        refresh_tag_filter();
+    });
 
-    let new_contents = lines.join("\n");
+    let tags_entry = gtk::Entry::new();
+    tags_entry.set_placeholder_text(Some("Tags, comma separated"));
+    tags_entry.set_text(&library.tags(path).join(", "));
+    let path_clone = path.to_string();
+    let popover_clone = popover.clone();
+    tags_entry.connect_activate(move |entry| {
+        popover_clone.popdown();
+        let tags: Vec<String> = entry.text().split(',').map(String::from).collect();
+        library::update(|library| library.set_tags(&path_clone, &tags));
+        refresh_tag_filter();
+    });
 
-    if let Ok(mut file) = fs::OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(&config_path)
-    {
-        let _ = writeln!(file, "{}", new_contents);
-    }
+    menu_box.append(&preview_button);
+    menu_box.append(&favorite_button);
+    menu_box.append(&tags_entry);
+    popover.set_child(Some(&menu_box));
+    popover.popup();
 }
 
-pub fn load_wallpaper_backend() -> Option<WallpaperBackend> {
-    let config_path = shellexpand::tilde(CONFIG_FILE).into_owned();
-    fs::File::open(config_path).ok().and_then(|mut file| {
-        let mut contents = String::new();
-        file.read_to_string(&mut contents).ok()?;
-        contents
-            .lines()
-            .find(|line| line.starts_with("backend = "))
-            .and_then(|line| {
-                let backend_str = line.trim_start_matches("backend = ");
-                match backend_str {
-                    "hyprpaper" => Some(WallpaperBackend::Hyprpaper),
-                    "swaybg" => Some(WallpaperBackend::Swaybg),
-                    "swww" => Some(WallpaperBackend::Swww),
-                    "wallutils" => Some(WallpaperBackend::Wallutils),
-                    "feh" => Some(WallpaperBackend::Feh),
-                    _ => None,
-                }
-            })
-    })
+#[derive(Clone)]
+struct EffectsControls {
+    blur: gtk::Scale,
+    dim: gtk::Scale,
+    vignette: gtk::Scale,
+    grayscale: gtk::CheckButton,
+    tint: gtk::Entry,
 }
 
-fn refresh_images(flowbox: &Rc<RefCell<FlowBox>>, image_loader: &Rc<RefCell<ImageLoader>>) {
-    let current_folder = {
-        let image_loader = image_loader.borrow();
-        image_loader.current_folder.clone()
-    };
+impl EffectsControls {
+    fn effects(&self) -> Effects {
+        let tint_text = self.tint.text();
+        let tint = effects::parse_color(&tint_text);
+        if tint.is_none() && !tint_text.is_empty() {
+            self.tint.add_css_class("error");
+        } else {
+            self.tint.remove_css_class("error");
+        }
 
-    if let Some(folder) = current_folder {
-        while let Some(child) = flowbox.borrow().first_child() {
-            flowbox.borrow().remove(&child);
+        Effects {
+            blur: self.blur.value(),
+            dim: self.dim.value(),
+            tint,
+            grayscale: self.grayscale.is_active(),
+            vignette: self.vignette.value(),
         }
-        load_images(&folder, flowbox, image_loader);
+    }
+
+    fn set(&self, effects: &Effects) {
+        self.blur.set_value(effects.blur);
+        self.dim.set_value(effects.dim);
+        self.vignette.set_value(effects.vignette);
+        self.grayscale.set_active(effects.grayscale);
+        self.tint.set_text(
+            &effects
+                .tint
+                .map(|(r, g, b, strength)| format!("#{:02x}{:02x}{:02x}:{}", r, g, b, strength))
+                .unwrap_or_default(),
+        );
     }
 }
 
-fn filter_wallpapers(flowbox: &Rc<RefCell<FlowBox>>, search_text: impl AsRef<str>) {
-    let search_text = search_text.as_ref().to_lowercase();
-    let flowbox = flowbox.borrow();
+fn effects_scale(bar: &GtkBox, label: &str, max: f64, step: f64) -> gtk::Scale {
+    let scale = gtk::Scale::with_range(gtk::Orientation::Horizontal, 0.0, max, step);
+    scale.set_width_request(100);
+    scale.set_draw_value(true);
+    bar.append(&gtk::Label::new(Some(label)));
+    bar.append(&scale);
+    scale
+}
 
-    let filter = move |child: &FlowBoxChild| {
-        if search_text.is_empty() {
-            return true;
-        }
+type PixbufBytes = (glib::Bytes, bool, i32, i32, i32);
 
-        if let Some(button) = child.child().and_downcast::<Button>() {
-            if let Some(tooltip) = button.tooltip_text() {
-                return tooltip.to_lowercase().contains(&search_text);
+fn pixbuf_bytes(pixbuf: &Pixbuf) -> PixbufBytes {
+    (
+        pixbuf.read_pixel_bytes(),
+        pixbuf.has_alpha(),
+        pixbuf.width(),
+        pixbuf.height(),
+        pixbuf.rowstride(),
+    )
+}
+
+fn pixbuf_from_bytes((bytes, has_alpha, width, height, rowstride): &PixbufBytes) -> Pixbuf {
+    Pixbuf::from_bytes(
+        bytes,
+        gtk::gdk_pixbuf::Colorspace::Rgb,
+        *has_alpha,
+        8,
+        *width,
+        *height,
+        *rowstride,
+    )
+}
+
+fn effects_bar(path: &str, picture: &gtk::Picture, texture: &Texture, preview: &Pixbuf) -> GtkBox {
+    let bar = GtkBox::new(gtk::Orientation::Horizontal, 5);
+    bar.set_margin_start(10);
+    bar.set_margin_end(10);
+    bar.set_margin_top(5);
+    bar.set_margin_bottom(5);
+
+    let controls = EffectsControls {
+        blur: effects_scale(&bar, "Blur", 50.0, 1.0),
+        dim: effects_scale(&bar, "Dim", 1.0, 0.05),
+        vignette: effects_scale(&bar, "Vignette", 1.0, 0.05),
+        grayscale: gtk::CheckButton::with_label("Grayscale"),
+        tint: gtk::Entry::new(),
+    };
+    controls.tint.set_placeholder_text(Some("Tint #rrggbb"));
+    controls.tint.set_width_chars(12);
+    bar.append(&controls.grayscale);
+    bar.append(&controls.tint);
+    controls.set(&effects::for_wallpaper(path));
+
+    let update = {
+        let controls = controls.clone();
+        let picture = picture.clone();
+        let texture = texture.clone();
+        let preview = pixbuf_bytes(preview);
+        let pending = Rc::new(Cell::new(false));
+        let generation = Rc::new(Cell::new(0u64));
+        Rc::new(move || {
+            if pending.replace(true) {
+                return;
             }
-        }
-        false
+
+            let controls = controls.clone();
+            let picture = picture.clone();
+            let texture = texture.clone();
+            let preview = preview.clone();
+            let pending = Rc::clone(&pending);
+            let generation = Rc::clone(&generation);
+            glib::timeout_add_local_once(
+                std::time::Duration::from_millis(EFFECTS_PREVIEW_DELAY_MS),
+                move || {
+                    pending.set(false);
+                    let current = generation.get() + 1;
+                    generation.set(current);
+
+                    let effects = controls.effects();
+                    if effects.is_empty() {
+                        picture.set_paintable(Some(&texture));
+                        return;
+                    }
+
+                    glib::spawn_future_local(async move {
+                        let rendered = gio::spawn_blocking(move || {
+                            effects
+                                .render(&pixbuf_from_bytes(&preview))
+                                .map(|rendered| pixbuf_bytes(&rendered))
+                        })
+                        .await
+                        .ok()
+                        .flatten();
+                        if let (Some(rendered), true) = (rendered, generation.get() == current) {
+                            let rendered = pixbuf_from_bytes(&rendered);
+                            picture.set_paintable(Some(&Texture::for_pixbuf(&rendered)));
+                        }
+                    });
+                },
+            );
+        })
     };
 
-    flowbox.set_filter_func(Box::new(filter));
+    for scale in [&controls.blur, &controls.dim, &controls.vignette] {
+        let update = update.clone();
+        scale.connect_value_changed(move |_| update());
+    }
+    let update_toggle = update.clone();
+    controls.grayscale.connect_toggled(move |_| update_toggle());
+    let update_tint = update.clone();
+    controls.tint.connect_changed(move |_| update_tint());
+
+    let spacer = GtkBox::new(gtk::Orientation::Horizontal, 0);
+    spacer.set_hexpand(true);
+    bar.append(&spacer);
+
+    let reset_button = Button::with_label("Reset");
+    reset_button.set_tooltip_text(Some("Use the global effects for this wallpaper"));
+    let path_reset = path.to_string();
+    let controls_reset = controls.clone();
+    reset_button.connect_clicked(move |_| {
+        library::update(|library| library.set_effects(&path_reset, None));
+        controls_reset.set(&effects::global());
+    });
+    bar.append(&reset_button);
+
+    let save_button = Button::with_label("Save");
+    save_button.set_tooltip_text(Some("Apply these effects whenever this wallpaper is set"));
+    let path_save = path.to_string();
+    save_button.connect_clicked(move |_| {
+        let effects = controls.effects();
+        library::update(|library| library.set_effects(&path_save, Some(effects.to_string())));
+        println!("Effects of {} set to: {}", path_save, effects);
+    });
+    bar.append(&save_button);
+
+    update();
+    bar
 }
 
 fn show_preview_window(path: &str, parent_widget: &impl IsA<gtk::Widget>) {
     let path = shellexpand::tilde(path).into_owned();
+    let path_with_tilde = path.replace(&std::env::var("HOME").unwrap_or_default(), "~");
     let parent = parent_widget.root().and_downcast::<gtk::Window>();
 
     glib::spawn_future_local(async move {
@@ -742,31 +1511,75 @@ fn show_preview_window(path: &str, parent_widget: &impl IsA<gtk::Widget>) {
         let path_buf = PathBuf::from(&path);
         let window_weak = window.downgrade();
 
-        let (sender, receiver) = crossbeam_channel::unbounded::<Result<Texture, String>>();
+        let (sender, receiver) =
+            crossbeam_channel::unbounded::<Result<(Texture, PixbufBytes), String>>();
 
         std::thread::spawn(move || {
+            let path_buf = slideshow::resolve(&path_buf.to_string_lossy())
+                .map(PathBuf::from)
+                .unwrap_or(path_buf);
             let file = gio::File::for_path(&path_buf);
-            match Texture::from_file(&file) {
-                Ok(texture) => {
-                    let _ = sender.send(Ok(texture));
-                }
-                Err(e) => {
-                    let _ = sender.send(Err(e.to_string()));
-                }
-            }
+            let result = Texture::from_file(&file)
+                .and_then(|texture| {
+                    let preview =
+                        Pixbuf::from_file_at_scale(&path_buf, EFFECTS_PREVIEW_SIZE, -1, true)?;
+                    Ok((texture, pixbuf_bytes(&preview)))
+                })
+                .map_err(|e| e.to_string());
+            let _ = sender.send(result);
         });
 
         glib::source::idle_add_local(move || match receiver.try_recv() {
             Ok(result) => {
                 if let Some(window) = window_weak.upgrade() {
                     match result {
-                        Ok(texture) => {
+                        Ok((texture, preview)) => {
+                            let preview = pixbuf_from_bytes(&preview);
                             let picture = gtk::Picture::for_paintable(&texture);
                             picture.set_can_shrink(true);
                             picture.set_keep_aspect_ratio(true);
                             picture.set_hexpand(true);
                             picture.set_vexpand(true);
-                            window.set_child(Some(&picture));
+                            picture.set_tooltip_text(Some(
+                                "Click to set the focal point kept in view when cropping",
+                            ));
+
+                            let effects_bar =
+                                effects_bar(&path_with_tilde, &picture, &texture, &preview);
+
+                            let focal_gesture = gtk::GestureClick::new();
+                            let path_with_tilde = path_with_tilde.clone();
+                            focal_gesture.connect_released(move |gesture, _, x, y| {
+                                let Some(picture) = gesture.widget() else {
+                                    return;
+                                };
+                                let (width, height) =
+                                    (picture.width() as f64, picture.height() as f64);
+                                let (texture_width, texture_height) =
+                                    (texture.width() as f64, texture.height() as f64);
+                                let scale = (width / texture_width).min(height / texture_height);
+                                let (shown_width, shown_height) =
+                                    (texture_width * scale, texture_height * scale);
+                                let focal_x = ((x - (width - shown_width) / 2.0) / shown_width)
+                                    .clamp(0.0, 1.0);
+                                let focal_y = ((y - (height - shown_height) / 2.0) / shown_height)
+                                    .clamp(0.0, 1.0);
+
+                                library::update(|library| {
+                                    library
+                                        .set_focal_point(&path_with_tilde, Some((focal_x, focal_y)))
+                                });
+                                println!(
+                                    "Focal point of {} set to: {:.2},{:.2}",
+                                    path_with_tilde, focal_x, focal_y
+                                );
+                            });
+                            picture.add_controller(focal_gesture);
+
+                            let content = GtkBox::new(gtk::Orientation::Vertical, 0);
+                            content.append(&picture);
+                            content.append(&effects_bar);
+                            window.set_child(Some(&content));
                         }
                         Err(error) => {
                             let error_label =
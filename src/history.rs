@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{atomic, config, state, WallpaperBackend};
+
+const HISTORY_FILE: &str = "history.json";
+const DEFAULT_HISTORY_SIZE: usize = 50;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub timestamp: u64,
+    pub path: String,
+    pub backend: String,
+    pub monitor: String,
+    #[serde(default)]
+    pub monitors: Vec<(String, String)>,
+}
+
+fn history_path() -> String {
+    state::file(HISTORY_FILE).to_string_lossy().into_owned()
+}
+
+fn history_size() -> usize {
+    config::get("history_size")
+        .and_then(|size| size.parse().ok())
+        .unwrap_or(DEFAULT_HISTORY_SIZE)
+}
+
+pub fn load() -> Vec<HistoryEntry> {
+    atomic::load_json(Path::new(&history_path())).unwrap_or_else(|e| {
+        eprintln!("Error loading history: {}", e);
+        Vec::new()
+    })
+}
+
+fn load_for_update() -> Option<Vec<HistoryEntry>> {
+    atomic::recover_json(Path::new(&history_path()))
+        .map_err(|e| eprintln!("Error loading history, not saving changes: {}", e))
+        .ok()
+}
+
+fn save(entries: &[HistoryEntry]) {
+    if let Ok(json) = serde_json::to_string_pretty(entries) {
+        if let Err(e) = atomic::write(Path::new(&history_path()), json.as_bytes()) {
+            eprintln!("Error saving history: {}", e);
+        }
+    }
+}
+
+pub fn record(path: &str, monitors: &[(String, String)], backend: WallpaperBackend) {
+    atomic::with_lock(Path::new(&history_path()), || {
+        record_locked(path, monitors, backend)
+    });
+}
+
+fn record_locked(path: &str, monitors: &[(String, String)], backend: WallpaperBackend) {
+    let Some(mut entries) = load_for_update() else {
+        return;
+    };
+
+    if entries
+        .first()
+        .is_some_and(|entry| entry.path == path && entry.monitors == monitors)
+    {
+        return;
+    }
+
+    let monitor = if monitors.is_empty() {
+        "all".to_string()
+    } else {
+        monitors
+            .iter()
+            .map(|(monitor, _)| monitor.as_str())
+            .collect::<Vec<_>>()
+            .join(",")
+    };
+
+    entries.insert(
+        0,
+        HistoryEntry {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            path: path.to_string(),
+            backend: backend.name().to_string(),
+            monitor,
+            monitors: monitors.to_vec(),
+        },
+    );
+    entries.truncate(history_size());
+    save(&entries);
+}
+
+pub fn previous() -> Option<HistoryEntry> {
+    load().into_iter().nth(1)
+}
+
+pub fn pop() {
+    atomic::with_lock(Path::new(&history_path()), || {
+        let Some(mut entries) = load_for_update() else {
+            return;
+        };
+        if !entries.is_empty() {
+            entries.remove(0);
+            save(&entries);
+        }
+    });
+}
+
+pub fn recent(limit: usize) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    for entry in load() {
+        if !paths.contains(&entry.path) {
+            paths.push(entry.path);
+        }
+        if paths.len() == limit {
+            break;
+        }
+    }
+    paths
+}
@@ -0,0 +1,173 @@
+use lazy_static::lazy_static;
+use parking_lot::Mutex;
+use std::time::Duration;
+use tokio::{process::Command as TokioCommand, task::JoinHandle};
+
+use crate::{config, palette, WallpaperBackend};
+
+const HOOKS_SECTION: &str = "Hooks";
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+
+lazy_static! {
+    static ref BACKGROUND_HOOKS: Mutex<Vec<JoinHandle<()>>> = Mutex::new(Vec::new());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HookEvent {
+    Pre,
+    Post,
+    Failure,
+}
+
+impl HookEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            HookEvent::Pre => "pre",
+            HookEvent::Post => "post",
+            HookEvent::Failure => "failure",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct HookContext {
+    pub path: String,
+    pub image: Option<String>,
+    pub monitors: Vec<String>,
+    pub backend: WallpaperBackend,
+    pub error: Option<String>,
+}
+
+impl HookContext {
+    pub fn new(path: &str, backend: WallpaperBackend) -> Self {
+        HookContext {
+            path: path.to_string(),
+            image: None,
+            monitors: Vec::new(),
+            backend,
+            error: None,
+        }
+    }
+
+    async fn env(&self, event: HookEvent) -> Vec<(&'static str, String)> {
+        let monitor = if self.monitors.is_empty() {
+            "all".to_string()
+        } else {
+            self.monitors.join(",")
+        };
+
+        let mut env = vec![
+            ("HYPRWALL_EVENT", event.name().to_string()),
+            ("HYPRWALL_PATH", self.path.clone()),
+            (
+                "HYPRWALL_IMAGE",
+                self.image.clone().unwrap_or_else(|| self.path.clone()),
+            ),
+            ("HYPRWALL_MONITOR", monitor),
+            ("HYPRWALL_BACKEND", self.backend.name().to_string()),
+        ];
+
+        let palette = match self.image.clone() {
+            Some(image) => tokio::task::spawn_blocking(move || palette::extract(&image).ok())
+                .await
+                .ok()
+                .flatten(),
+            None => None,
+        };
+        if let Some(palette) = palette {
+            let colors: Vec<String> = palette.colors.iter().copied().map(palette::hex).collect();
+            env.push(("HYPRWALL_PALETTE", colors.join(" ")));
+            env.push(("HYPRWALL_BACKGROUND", palette::hex(palette.background())));
+            env.push(("HYPRWALL_FOREGROUND", palette::hex(palette.foreground())));
+        }
+
+        if let Some(error) = &self.error {
+            env.push(("HYPRWALL_ERROR", error.clone()));
+        }
+
+        env
+    }
+}
+
+fn timeout() -> Duration {
+    let secs = config::section(HOOKS_SECTION)
+        .into_iter()
+        .find(|(key, _)| key == "timeout")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(DEFAULT_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+async fn run_command(
+    event: HookEvent,
+    command: String,
+    env: Vec<(&'static str, String)>,
+    timeout: Duration,
+) {
+    let mut child = match TokioCommand::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .envs(env)
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            eprintln!("Failed to run {} hook `{}`: {}", event.name(), command, e);
+            return;
+        }
+    };
+
+    match tokio::time::timeout(timeout, child.wait()).await {
+        Ok(Ok(status)) if !status.success() => {
+            eprintln!("{} hook `{}` exited with {}", event.name(), command, status);
+        }
+        Ok(Err(e)) => eprintln!(
+            "Failed to wait for {} hook `{}`: {}",
+            event.name(),
+            command,
+            e
+        ),
+        Err(_) => {
+            eprintln!(
+                "{} hook `{}` timed out after {}s, killing it",
+                event.name(),
+                command,
+                timeout.as_secs()
+            );
+            let _ = child.kill().await;
+        }
+        Ok(Ok(_)) => {}
+    }
+}
+
+pub async fn run(event: HookEvent, context: &HookContext) {
+    let commands: Vec<String> = config::section(HOOKS_SECTION)
+        .into_iter()
+        .filter(|(key, _)| key == event.name())
+        .map(|(_, command)| command)
+        .collect();
+    if commands.is_empty() {
+        return;
+    }
+
+    let env = context.env(event).await;
+    let timeout = timeout();
+
+    for command in commands {
+        let task = run_command(event, command, env.clone(), timeout);
+        if event == HookEvent::Pre {
+            task.await;
+        } else {
+            let mut background = BACKGROUND_HOOKS.lock();
+            background.retain(|handle| !handle.is_finished());
+            background.push(tokio::spawn(task));
+        }
+    }
+}
+
+pub async fn wait() {
+    let handles = std::mem::take(&mut *BACKGROUND_HOOKS.lock());
+    for handle in handles {
+        let _ = handle.await;
+    }
+}
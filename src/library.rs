@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+use crate::atomic;
+
+const LIBRARY_FILE: &str = "~/.config/hyprwall/library.json";
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct LibraryEntry {
+    #[serde(default)]
+    pub favorite: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub focal_point: Option<(f64, f64)>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effects: Option<String>,
+}
+
+impl LibraryEntry {
+    fn is_empty(&self) -> bool {
+        !self.favorite
+            && self.tags.is_empty()
+            && self.focal_point.is_none()
+            && self.effects.is_none()
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Library {
+    #[serde(default)]
+    pub wallpapers: BTreeMap<String, LibraryEntry>,
+}
+
+fn library_path() -> String {
+    shellexpand::tilde(LIBRARY_FILE).into_owned()
+}
+
+pub fn load() -> Library {
+    atomic::load_json(Path::new(&library_path())).unwrap_or_else(|e| {
+        eprintln!("Error loading library: {}", e);
+        Library::default()
+    })
+}
+
+pub fn update<T>(f: impl FnOnce(&mut Library) -> T) -> T {
+    atomic::with_lock(Path::new(&library_path()), || {
+        match atomic::recover_json(Path::new(&library_path())) {
+            Ok(mut library) => {
+                let result = f(&mut library);
+                library.save();
+                result
+            }
+            Err(e) => {
+                eprintln!("Error loading library, not saving changes: {}", e);
+                f(&mut Library::default())
+            }
+        }
+    })
+}
+
+impl Library {
+    fn save(&mut self) {
+        self.wallpapers.retain(|_, entry| !entry.is_empty());
+
+        if let Ok(json) = serde_json::to_string_pretty(self) {
+            if let Err(e) = atomic::write(Path::new(&library_path()), json.as_bytes()) {
+                eprintln!("Error saving library: {}", e);
+            }
+        }
+    }
+
+    pub fn is_favorite(&self, path: &str) -> bool {
+        self.wallpapers
+            .get(path)
+            .is_some_and(|entry| entry.favorite)
+    }
+
+    pub fn tags(&self, path: &str) -> &[String] {
+        self.wallpapers
+            .get(path)
+            .map_or(&[], |entry| entry.tags.as_slice())
+    }
+
+    pub fn focal_point(&self, path: &str) -> Option<(f64, f64)> {
+        self.wallpapers
+            .get(path)
+            .and_then(|entry| entry.focal_point)
+    }
+
+    pub fn set_focal_point(&mut self, path: &str, focal_point: Option<(f64, f64)>) {
+        self.wallpapers
+            .entry(path.to_string())
+            .or_default()
+            .focal_point = focal_point;
+    }
+
+    pub fn effects(&self, path: &str) -> Option<&str> {
+        self.wallpapers
+            .get(path)
+            .and_then(|entry| entry.effects.as_deref())
+    }
+
+    pub fn set_effects(&mut self, path: &str, effects: Option<String>) {
+        self.wallpapers.entry(path.to_string()).or_default().effects = effects;
+    }
+
+    pub fn has_tag(&self, path: &str, tag: &str) -> bool {
+        self.tags(path).iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    pub fn all_tags(&self) -> BTreeSet<String> {
+        self.wallpapers
+            .values()
+            .flat_map(|entry| entry.tags.iter().cloned())
+            .collect()
+    }
+
+    pub fn toggle_favorite(&mut self, path: &str) -> bool {
+        let entry = self.wallpapers.entry(path.to_string()).or_default();
+        entry.favorite = !entry.favorite;
+        entry.favorite
+    }
+
+    pub fn add_tags(&mut self, path: &str, tags: &[String]) {
+        let entry = self.wallpapers.entry(path.to_string()).or_default();
+        for tag in tags.iter().map(|t| t.trim()).filter(|t| !t.is_empty()) {
+            if !entry.tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+                entry.tags.push(tag.to_string());
+            }
+        }
+    }
+
+    pub fn remove_tags(&mut self, path: &str, tags: &[String]) {
+        if let Some(entry) = self.wallpapers.get_mut(path) {
+            entry
+                .tags
+                .retain(|t| !tags.iter().any(|tag| t.eq_ignore_ascii_case(tag.trim())));
+        }
+    }
+
+    pub fn set_tags(&mut self, path: &str, tags: &[String]) {
+        if let Some(entry) = self.wallpapers.get_mut(path) {
+            entry.tags.clear();
+        }
+        self.add_tags(path, tags);
+    }
+}
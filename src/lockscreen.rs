@@ -0,0 +1,298 @@
+use std::fs;
+use std::path::Path;
+
+use crate::{atomic, config};
+
+const HYPRLOCK_CONFIG: &str = "~/.config/hypr/hyprlock.conf";
+const SWAYLOCK_CONFIG: &str = "~/.config/swaylock/config";
+
+fn config_file(key: &str, default: &str) -> String {
+    shellexpand::tilde(&config::get(key).unwrap_or_else(|| default.to_string())).into_owned()
+}
+
+fn entry_key(entry: &str) -> Option<&str> {
+    entry.split_once('=').map(|(key, _)| key.trim())
+}
+
+fn set_hyprlock_path(contents: &str, wallpaper: &str) -> String {
+    let path_entry = format!("path = {}", wallpaper);
+    let mut lines = Vec::new();
+    let mut depth = 0;
+    let mut in_background = false;
+    let mut pending_indent = None;
+    let mut has_path = false;
+    let mut block_indent = String::new();
+    let mut found = false;
+
+    for line in contents.lines() {
+        let code = line.split('#').next().unwrap_or_default();
+        let comment = line[code.len()..].trim();
+        let trimmed = code.trim();
+        let indent = &line[..line.len() - line.trim_start().len()];
+
+        let mut opens = false;
+        if depth == 0 && !in_background {
+            let after_name = trimmed.strip_prefix("background").map(str::trim_start);
+            if after_name == Some("") {
+                pending_indent = Some(indent.to_string());
+                lines.push(line.to_string());
+                continue;
+            }
+            if after_name.is_some_and(|rest| rest.starts_with('{')) {
+                opens = true;
+                block_indent = indent.to_string();
+            } else if let Some(background_indent) =
+                pending_indent.take().filter(|_| trimmed.starts_with('{'))
+            {
+                opens = true;
+                block_indent = background_indent;
+            } else if !trimmed.is_empty() {
+                pending_indent = None;
+            }
+        }
+
+        if opens {
+            in_background = true;
+            has_path = false;
+            found = true;
+        }
+
+        if !in_background {
+            depth = (depth + code.matches('{').count() as i32 - code.matches('}').count() as i32)
+                .max(0);
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let (head, rest) = match code.find('{').filter(|_| opens) {
+            Some(brace) => code.split_at(brace + 1),
+            None => ("", code),
+        };
+        let (body, tail) = match rest.find('}') {
+            Some(brace) => rest.split_at(brace),
+            None => (rest, ""),
+        };
+        let body = body.trim();
+        let closes = !tail.is_empty();
+
+        let is_path = entry_key(body) == Some("path");
+        let needs_path = closes && !has_path && !is_path;
+        has_path |= is_path;
+        if closes {
+            in_background = false;
+        }
+
+        if !is_path && !needs_path && (body.is_empty() || !(opens || closes)) {
+            lines.push(line.to_string());
+            continue;
+        }
+
+        let entry_indent = if opens || closes {
+            format!("{}    ", block_indent)
+        } else {
+            indent.to_string()
+        };
+        let mut rebuilt = Vec::new();
+        if opens {
+            rebuilt.push(format!("{}{}", indent, head.trim()));
+        }
+        if is_path {
+            rebuilt.push(format!("{}{}", entry_indent, path_entry));
+        } else if !body.is_empty() {
+            rebuilt.push(format!("{}{}", entry_indent, body));
+        }
+        if needs_path {
+            rebuilt.push(format!("{}{}", entry_indent, path_entry));
+        }
+        if closes {
+            rebuilt.push(format!("{}{}", block_indent, tail.trim()));
+        }
+        if let Some(last) = rebuilt.last_mut().filter(|_| !comment.is_empty()) {
+            last.push(' ');
+            last.push_str(comment);
+        }
+        lines.extend(rebuilt);
+    }
+
+    if !found {
+        lines.push(String::new());
+        lines.push("background {".to_string());
+        lines.push(format!("    {}", path_entry));
+        lines.push("}".to_string());
+    }
+
+    lines.join("\n") + "\n"
+}
+
+fn update_hyprlock(wallpaper: &str) -> Result<(), String> {
+    let target = config_file("hyprlock_config", HYPRLOCK_CONFIG);
+    let contents =
+        fs::read_to_string(&target).map_err(|e| format!("Failed to read {}: {}", target, e))?;
+
+    let updated = set_hyprlock_path(&contents, wallpaper);
+    if updated != contents {
+        atomic::write(Path::new(&target), updated.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn set_swaylock_images(contents: &str, wallpapers: &[(Option<String>, String)]) -> String {
+    let images = wallpapers.iter().map(|(monitor, path)| match monitor {
+        Some(monitor) => format!("image={}:{}", monitor, path),
+        None => format!("image={}", path),
+    });
+
+    let mut lines = Vec::new();
+    let mut images = Some(images);
+    for line in contents.lines() {
+        let key = line.trim().split('=').next().map(str::trim);
+        if key == Some("image") {
+            lines.extend(images.take().into_iter().flatten());
+        } else {
+            lines.push(line.to_string());
+        }
+    }
+    lines.extend(images.into_iter().flatten());
+
+    lines.join("\n") + "\n"
+}
+
+fn update_swaylock(wallpapers: &[(Option<String>, String)]) -> Result<(), String> {
+    let target = config_file("swaylock_config", SWAYLOCK_CONFIG);
+    let contents = fs::read_to_string(&target).unwrap_or_default();
+
+    if let Some(parent) = Path::new(&target).parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+
+    let updated = set_swaylock_images(&contents, wallpapers);
+    if updated != contents {
+        atomic::write(Path::new(&target), updated.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn update_symlink(wallpaper: &str) -> Result<(), String> {
+    let link = config::get("lockscreen_link")
+        .map(|link| shellexpand::tilde(&link).into_owned())
+        .ok_or("lockscreen_link is not set")?;
+    let link_path = Path::new(&link);
+
+    if link_path.exists() && !link_path.is_symlink() {
+        return Err(format!("{} exists and is not a symlink", link));
+    }
+    atomic::replace_symlink(link_path, wallpaper)
+}
+
+pub fn enabled() -> bool {
+    config::get("lockscreen").is_some_and(|targets| !targets.trim().is_empty())
+}
+
+pub fn sync(wallpapers: &[(Option<String>, String)]) {
+    let Some(targets) = config::get("lockscreen") else {
+        return;
+    };
+    let Some((_, wallpaper)) = wallpapers.first() else {
+        return;
+    };
+
+    for target in targets.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+        let result = match target {
+            "hyprlock" => update_hyprlock(wallpaper),
+            "swaylock" => update_swaylock(wallpapers),
+            "symlink" => update_symlink(wallpaper),
+            other => Err(format!("Unknown lock screen: {}", other)),
+        };
+
+        if let Err(e) = result {
+            eprintln!("Error updating lock screen: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_the_path_of_a_block() {
+        let contents = "background {\n    monitor =\n    path = /old.png # wallpaper\n}\n";
+        assert_eq!(
+            set_hyprlock_path(contents, "/new.png"),
+            "background {\n    monitor =\n    path = /new.png # wallpaper\n}\n"
+        );
+    }
+
+    #[test]
+    fn replaces_the_path_of_an_inline_block() {
+        assert_eq!(
+            set_hyprlock_path("background { path = /old.png }\n", "/new.png"),
+            "background {\n    path = /new.png\n}\n"
+        );
+        assert_eq!(
+            set_hyprlock_path("  background {}\n", "/new.png"),
+            "  background {\n      path = /new.png\n  }\n"
+        );
+    }
+
+    #[test]
+    fn handles_a_brace_on_the_next_line() {
+        let contents = "background\n{\n    path = /old.png\n}\n";
+        assert_eq!(
+            set_hyprlock_path(contents, "/new.png"),
+            "background\n{\n    path = /new.png\n}\n"
+        );
+        assert_eq!(
+            set_hyprlock_path("background\n{\n    blur_passes = 2 }\n", "/new.png"),
+            "background\n{\n    blur_passes = 2\n    path = /new.png\n}\n"
+        );
+    }
+
+    #[test]
+    fn adds_a_path_to_blocks_without_one() {
+        let contents =
+            "input-field {\n    size = 200, 50\n}\nbackground {\n    color = rgb(0, 0, 0)\n}\n";
+        assert_eq!(
+            set_hyprlock_path(contents, "/new.png"),
+            "input-field {\n    size = 200, 50\n}\nbackground {\n    color = rgb(0, 0, 0)\n    path = /new.png\n}\n"
+        );
+    }
+
+    #[test]
+    fn appends_a_block_when_there_is_none() {
+        assert_eq!(
+            set_hyprlock_path("general {\n    hide_cursor = true\n}\n", "/new.png"),
+            "general {\n    hide_cursor = true\n}\n\nbackground {\n    path = /new.png\n}\n"
+        );
+    }
+
+    #[test]
+    fn is_stable_once_updated() {
+        for contents in [
+            "background { path = /old.png }\n",
+            "background\n{\n}\n",
+            "background {\n    path = /old.png\n}\nbackground {\n}\n",
+        ] {
+            let updated = set_hyprlock_path(contents, "/new.png");
+            assert_eq!(set_hyprlock_path(&updated, "/new.png"), updated);
+        }
+    }
+
+    #[test]
+    fn writes_one_swaylock_image_per_output() {
+        let contents = "color=000000\nimage=/old.png\nimage=DP-1:/older.png\nscaling=fill\n";
+        let wallpapers = [
+            (Some("DP-1".to_string()), "/left.png".to_string()),
+            (Some("DP-2".to_string()), "/right.png".to_string()),
+        ];
+        assert_eq!(
+            set_swaylock_images(contents, &wallpapers),
+            "color=000000\nimage=DP-1:/left.png\nimage=DP-2:/right.png\nscaling=fill\n"
+        );
+        assert_eq!(
+            set_swaylock_images("", &[(None, "/new.png".to_string())]),
+            "image=/new.png\n"
+        );
+    }
+}
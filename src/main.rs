@@ -1,20 +1,39 @@
+mod atomic;
+mod cache;
+mod config;
+mod effects;
+mod fit;
+mod formats;
 mod gui;
-
-use clap::Parser;
-use gtk::{prelude::*, Application};
+mod history;
+mod hooks;
+mod library;
+mod lockscreen;
+mod palette;
+mod profile;
+mod reload;
+mod render;
+mod schedule;
+mod shuffle;
+mod slideshow;
+mod sort;
+mod state;
+
+use chrono::TimeZone;
+use clap::{error::ErrorKind, CommandFactory, Parser, Subcommand};
+use gtk::{gdk_pixbuf::Pixbuf, prelude::*, Application};
 use lazy_static::lazy_static;
 use parking_lot::Mutex;
-use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
 use shellexpand::tilde;
-use std::fs::{self, OpenOptions};
-use std::io::{Read, Write};
+use std::fs;
 use std::path::{Path, PathBuf};
 use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::process::Command as TokioCommand;
 use tokio::runtime::Runtime;
 
 lazy_static! {
-    static ref MONITORS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    static ref MONITORS: Mutex<Vec<Monitor>> = Mutex::new(Vec::new());
     static ref CURRENT_BACKEND: Mutex<WallpaperBackend> = Mutex::new(WallpaperBackend::None);
 }
 
@@ -28,22 +47,151 @@ pub enum WallpaperBackend {
     Feh,
 }
 
+impl WallpaperBackend {
+    pub fn name(&self) -> &'static str {
+        match self {
+            WallpaperBackend::None => "none",
+            WallpaperBackend::Hyprpaper => "hyprpaper",
+            WallpaperBackend::Swaybg => "swaybg",
+            WallpaperBackend::Swww => "swww",
+            WallpaperBackend::Wallutils => "wallutils",
+            WallpaperBackend::Feh => "feh",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "none" => Some(WallpaperBackend::None),
+            "hyprpaper" => Some(WallpaperBackend::Hyprpaper),
+            "swaybg" => Some(WallpaperBackend::Swaybg),
+            "swww" => Some(WallpaperBackend::Swww),
+            "wallutils" => Some(WallpaperBackend::Wallutils),
+            "feh" => Some(WallpaperBackend::Feh),
+            _ => None,
+        }
+    }
+
+    pub fn supports_monitors(&self) -> bool {
+        matches!(
+            self,
+            WallpaperBackend::Hyprpaper | WallpaperBackend::Swaybg | WallpaperBackend::Swww
+        )
+    }
+
+    pub fn daemon_process(&self) -> Option<&'static str> {
+        match self {
+            WallpaperBackend::Hyprpaper => Some("hyprpaper"),
+            WallpaperBackend::Swaybg => Some("swaybg"),
+            WallpaperBackend::Swww => Some("swww-daemon"),
+            WallpaperBackend::Wallutils | WallpaperBackend::Feh | WallpaperBackend::None => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Monitor {
+    pub name: String,
+    #[serde(default)]
+    pub width: i32,
+    #[serde(default)]
+    pub height: i32,
+    #[serde(default)]
+    pub x: i32,
+    #[serde(default)]
+    pub y: i32,
+    #[serde(default = "default_scale")]
+    pub scale: f64,
+    #[serde(default)]
+    pub transform: i32,
+}
+
+fn default_scale() -> f64 {
+    1.0
+}
+
+#[derive(Clone)]
+struct Assignment {
+    monitor: Option<String>,
+    path: String,
+}
+
+#[derive(Serialize)]
+struct WallpaperInfo {
+    path: String,
+    name: String,
+    format: Option<String>,
+    width: Option<i32>,
+    height: Option<i32>,
+    size: u64,
+    modified: Option<u64>,
+    favorite: bool,
+    tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct MonitorStatus {
+    name: String,
+    wallpaper: Option<String>,
+}
+
+#[derive(Serialize)]
+struct DaemonStatus {
+    process: &'static str,
+    running: bool,
+}
+
+#[derive(Serialize)]
+struct Status {
+    backend: &'static str,
+    daemon: Option<DaemonStatus>,
+    folder: Option<String>,
+    wallpaper: Option<String>,
+    monitors: Vec<MonitorStatus>,
+}
+
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
 struct Cli {
-    #[arg(short = 'r', long, help = "Restore the last selected wallpaper")]
+    #[command(subcommand)]
+    command: Option<Commands>,
+
+    #[arg(
+        short = 'r',
+        long,
+        group = "action",
+        help = "Restore the last selected wallpaper (alias for `restore`)"
+    )]
     restore: bool,
 
-    #[arg(short = 'R', long, help = "Set a random wallpaper")]
+    #[arg(
+        short = 'R',
+        long,
+        group = "action",
+        help = "Set a random wallpaper (alias for `random`)"
+    )]
     random: bool,
 
+    #[arg(
+        short = 't',
+        long,
+        requires = "random",
+        help = "Only pick wallpapers with this tag (should be used with -R)"
+    )]
+    tag: Option<String>,
+
     #[arg(short = 'b', long, help = "Set the wallpaper backend", default_value = None)]
     backend: Option<String>,
 
     #[arg(short = 'f', long, help = "Set the wallpaper folder", default_value = None)]
     folder: Option<PathBuf>,
 
-    #[arg(short = 'w', long, help = "Set a specific wallpaper", default_value = None)]
+    #[arg(
+        short = 'w',
+        long,
+        group = "action",
+        help = "Set a specific wallpaper (alias for `set`)",
+        default_value = None
+    )]
     wallpaper: Option<PathBuf>,
 
     #[arg(short = 'g', long, help = "Generate the config file")]
@@ -58,38 +206,152 @@ struct Cli {
 
     #[arg(short = 'C', long, help = "Display copyright information")]
     copyright: bool,
+
+    #[arg(long, global = true, help = "Print machine-readable JSON output")]
+    json: bool,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    #[command(about = "Set a specific wallpaper")]
+    Set { path: PathBuf },
+
+    #[command(about = "Set a random wallpaper")]
+    Random {
+        #[arg(short, long, help = "Only pick wallpapers with this tag")]
+        tag: Option<String>,
+    },
+
+    #[command(about = "Restore the last selected wallpaper")]
+    Restore,
+
+    #[command(about = "List the wallpapers in the wallpaper folder")]
+    List {
+        #[arg(short, long, help = "Only list wallpapers with this tag")]
+        tag: Option<String>,
+    },
+
+    #[command(about = "Show the current backend, folder and wallpaper")]
+    Status,
+
+    #[command(about = "Set the next wallpaper in the wallpaper folder")]
+    Next,
+
+    #[command(about = "Set the previous wallpaper in the wallpaper folder")]
+    Prev,
+
+    #[command(about = "Show recently set wallpapers")]
+    History,
+
+    #[command(about = "Go back to the previously set wallpaper")]
+    Undo,
+
+    #[command(about = "Add or remove a wallpaper from favorites")]
+    Favorite { path: PathBuf },
+
+    #[command(about = "Set the point of a wallpaper kept in view when it's cropped")]
+    FocalPoint {
+        path: PathBuf,
+        #[arg(help = "Horizontal and vertical position from 0 to 1, e.g. 0.5,0.3, or `reset`")]
+        point: String,
+    },
+
+    #[command(about = "Set the effects applied to a wallpaper before it's displayed")]
+    Effects {
+        path: PathBuf,
+        #[arg(long, help = "Gaussian blur radius in pixels at 1080p")]
+        blur: Option<f64>,
+        #[arg(long, help = "Darken the wallpaper, from 0 to 1")]
+        dim: Option<f64>,
+        #[arg(
+            long,
+            help = "Tint color as #rrggbb, optionally with a strength, e.g. #ff8800:0.4"
+        )]
+        tint: Option<String>,
+        #[arg(long, help = "Convert the wallpaper to grayscale")]
+        grayscale: bool,
+        #[arg(long, help = "Darken the corners, from 0 to 1")]
+        vignette: Option<f64>,
+        #[arg(long, help = "Remove the wallpaper's effects and use the global ones")]
+        reset: bool,
+    },
+
+    #[command(about = "Manage wallpaper tags")]
+    Tag {
+        #[command(subcommand)]
+        action: TagAction,
+    },
+
+    #[command(about = "Show the color palette of a wallpaper (the current one by default)")]
+    Palette { path: Option<PathBuf> },
+
+    #[command(about = "Switch between named setups of backend, folder and wallpapers")]
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+
+    #[command(about = "Show today's wallpaper schedule")]
+    Schedule,
+
+    #[command(about = "Run in the background and apply the wallpaper schedule")]
+    Daemon,
+
+    #[command(about = "Read or change config values")]
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum TagAction {
+    #[command(about = "Add tags to a wallpaper")]
+    Add {
+        path: PathBuf,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+
+    #[command(about = "Remove tags from a wallpaper")]
+    Remove {
+        path: PathBuf,
+        #[arg(required = true)]
+        tags: Vec<String>,
+    },
+
+    #[command(about = "List the tags of a wallpaper, or all tags")]
+    List { path: Option<PathBuf> },
+}
+
+#[derive(Subcommand)]
+enum ProfileAction {
+    #[command(about = "Apply a profile")]
+    Use { name: String },
+
+    #[command(about = "List the configured profiles")]
+    List,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    #[command(about = "Print a config value")]
+    Get { key: String },
+
+    #[command(about = "Change a config value")]
+    Set { key: String, value: String },
 }
 
 fn main() {
     let cli = Cli::parse();
-
-    let cli = Cli {
-        wallpaper: cli
-            .wallpaper
-            .map(|p| {
-                if p.is_relative() {
-                    std::env::current_dir()
-                        .map(|cur| cur.join(p.clone()))
-                        .unwrap_or(p)
-                } else {
-                    p
-                }
-            })
-            .map(|p| PathBuf::from(shellexpand::tilde(&p.to_string_lossy()).into_owned())),
-        folder: cli
-            .folder
-            .map(|p| {
-                if p.is_relative() {
-                    std::env::current_dir()
-                        .map(|cur| cur.join(p.clone()))
-                        .unwrap_or(p)
-                } else {
-                    p
-                }
-            })
-            .map(|p| PathBuf::from(shellexpand::tilde(&p.to_string_lossy()).into_owned())),
-        ..cli
-    };
+    if cli.command.is_some() && (cli.restore || cli.random || cli.wallpaper.is_some()) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "-w, -r and -R can't be combined with a subcommand",
+            )
+            .exit();
+    }
 
     let rt = Runtime::new().expect("Failed to create Tokio runtime");
     let _guard = rt.enter();
@@ -133,169 +395,207 @@ along with this program; if not, see
     if !config_exists() {
         generate_config();
     }
+    state::migrate();
 
     load_wallpaper_backend();
 
-    if let Some(backend) = cli.backend {
-        set_backend(&backend);
+    if let Some(backend) = &cli.backend {
+        set_backend(backend);
     }
 
-    if let Some(folder) = cli.folder {
-        set_folder(&folder);
+    if let Some(folder) = cli.folder.clone() {
+        set_folder(&absolute_path(folder));
     }
 
-    if let Some(wallpaper) = &cli.wallpaper {
-        let wallpaper_path = wallpaper
-            .to_string_lossy()
-            .replace(&std::env::var("HOME").unwrap_or_default(), "~");
-
-        let rt = Runtime::new().expect("Failed to create Tokio runtime");
-        rt.block_on(async {
-            let current_backend = *CURRENT_BACKEND.lock();
-            if current_backend == WallpaperBackend::None {
-                eprintln!("No wallpaper backend set. Please set a backend using the -b or --backend option.");
-                return;
-            }
-
-            let previous_backend = current_backend;
-            drop_all_wallpapers(previous_backend).await;
-            kill_previous_backend(previous_backend).await;
-
-            match set_wallpaper_internal(&wallpaper_path).await {
-                Ok(_) => {
-                    println!("Wallpaper set successfully: {}", wallpaper_path);
-                    gui::save_last_wallpaper(&wallpaper_path);
-                }
-                Err(e) => eprintln!("Error setting wallpaper: {}", e),
-            }
-        });
-        return;
-    }
+    let command = cli.command.or(if let Some(wallpaper) = cli.wallpaper {
+        Some(Commands::Set { path: wallpaper })
+    } else if cli.restore {
+        Some(Commands::Restore)
+    } else if cli.random {
+        Some(Commands::Random { tag: cli.tag })
+    } else {
+        None
+    });
 
-    if cli.restore {
-        restore_last_wallpaper();
-        return;
+    match command {
+        Some(Commands::Set { path }) => set_wallpaper_cli(&absolute_path(path)),
+        Some(Commands::Random { tag }) => set_random_wallpaper(tag.as_deref()),
+        Some(Commands::Restore) => restore_last_wallpaper(),
+        Some(Commands::List { tag }) => list_wallpapers(tag.as_deref(), cli.json),
+        Some(Commands::Status) => print_status(cli.json),
+        Some(Commands::Next) => step_wallpaper(1),
+        Some(Commands::Prev) => step_wallpaper(-1),
+        Some(Commands::History) => print_history(cli.json),
+        Some(Commands::Undo) => undo_wallpaper(),
+        Some(Commands::Favorite { path }) => toggle_favorite(&absolute_path(path)),
+        Some(Commands::FocalPoint { path, point }) => set_focal_point(&absolute_path(path), &point),
+        Some(Commands::Effects {
+            path,
+            blur,
+            dim,
+            tint,
+            grayscale,
+            vignette,
+            reset,
+        }) => {
+            let spec = [
+                blur.map(|blur| format!("blur={}", blur)),
+                dim.map(|dim| format!("dim={}", dim)),
+                tint.map(|tint| format!("tint={}", tint)),
+                grayscale.then(|| "grayscale".to_string()),
+                vignette.map(|vignette| format!("vignette={}", vignette)),
+            ]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(",");
+            set_effects(&absolute_path(path), &spec, reset)
+        }
+        Some(Commands::Tag { action }) => match action {
+            TagAction::Add { path, tags } => add_tags(&absolute_path(path), &tags),
+            TagAction::Remove { path, tags } => remove_tags(&absolute_path(path), &tags),
+            TagAction::List { path } => list_tags(path.map(absolute_path).as_deref()),
+        },
+        Some(Commands::Palette { path }) => print_palette(path.map(absolute_path), cli.json),
+        Some(Commands::Profile { action }) => match action {
+            ProfileAction::Use { name } => use_profile(&name),
+            ProfileAction::List => profile::print_profiles(),
+        },
+        Some(Commands::Schedule) => schedule::print_schedule(),
+        Some(Commands::Daemon) => rt.block_on(schedule::run()),
+        Some(Commands::Config { action }) => match action {
+            ConfigAction::Get { key } => config_get(&key),
+            ConfigAction::Set { key, value } => config_set(&key, &value),
+        },
+        None => {
+            let app = Application::builder()
+                .application_id("hyprutils.hyprwall")
+                .build();
+
+            app.connect_activate(gui::build_ui);
+            app.run();
+        }
     }
+}
 
-    if cli.random {
-        set_random_wallpaper();
-        return;
+fn absolute_path(path: PathBuf) -> PathBuf {
+    let path = PathBuf::from(shellexpand::tilde(&path.to_string_lossy()).into_owned());
+    if path.is_relative() {
+        std::env::current_dir()
+            .map(|cur| cur.join(&path))
+            .unwrap_or(path)
+    } else {
+        path
     }
+}
 
-    let app = Application::builder()
-        .application_id("hyprutils.hyprwall")
-        .build();
-
-    app.connect_activate(gui::build_ui);
-    app.run();
+fn tilde_path(path: &Path) -> String {
+    path.to_string_lossy()
+        .replace(&std::env::var("HOME").unwrap_or_default(), "~")
 }
 
 fn config_exists() -> bool {
-    let config_path = tilde("~/.config/hyprwall/config.ini").into_owned();
-    Path::new(&config_path).exists()
+    Path::new(&config::config_path()).exists()
 }
 
 fn generate_config() {
-    let config_path = tilde("~/.config/hyprwall/config.ini").into_owned();
+    let config_path = config::config_path();
     let config_dir = Path::new(&config_path).parent().unwrap();
     std::fs::create_dir_all(config_dir).expect("Failed to create config directory");
 
     let default_config = r#"[Settings]
 folder = none
 backend = none
-last_wallpaper = none
 "#;
 
-    std::fs::write(&config_path, default_config).expect("Failed to write config file");
+    atomic::write(Path::new(&config_path), default_config.as_bytes())
+        .expect("Failed to write config file");
     println!("Config file generated at: {}", config_path);
 }
 
 fn set_backend(backend: &str) {
-    let backend = match backend.to_lowercase().as_str() {
-        "hyprpaper" => WallpaperBackend::Hyprpaper,
-        "swaybg" => WallpaperBackend::Swaybg,
-        "swww" => WallpaperBackend::Swww,
-        "wallutils" => WallpaperBackend::Wallutils,
-        "feh" => WallpaperBackend::Feh,
-        _ => {
-            eprintln!("Invalid backend specified. Using default (Hyprpaper).");
-            WallpaperBackend::Hyprpaper
-        }
-    };
+    if let Err(e) = validate_setting("backend", backend) {
+        eprintln!("{}", e);
+        return;
+    }
+    let backend = WallpaperBackend::from_name(backend).unwrap_or(WallpaperBackend::None);
     set_wallpaper_backend(backend);
     println!("Wallpaper backend set to: {:?}", backend);
 }
 
 fn set_folder(folder: &Path) {
-    let folder_str = folder.to_string_lossy();
-    let folder_expanded = shellexpand::tilde(&folder_str).into_owned();
-    let folder_path = Path::new(&folder_expanded);
-
-    if folder_path.is_dir() {
-        let config_path = tilde("~/.config/hyprwall/config.ini").into_owned();
-        let mut contents = String::new();
+    if folder.is_dir() {
+        let folder_with_tilde = tilde_path(folder);
+        config::set("folder", &folder_with_tilde);
+        gui::save_last_path(folder);
+        println!("Wallpaper folder set to: {}", folder_with_tilde);
+    } else {
+        eprintln!("Specified folder does not exist or is not a directory.");
+    }
+}
 
-        if let Ok(mut file) = fs::File::open(&config_path) {
-            file.read_to_string(&mut contents).unwrap_or_default();
-        }
+fn set_wallpaper_cli(wallpaper: &Path) {
+    if !formats::is_wallpaper(wallpaper) {
+        eprintln!(
+            "Error setting wallpaper: {} is not a supported image",
+            wallpaper.display()
+        );
+        return;
+    }
 
-        let mut lines: Vec<String> = contents.lines().map(String::from).collect();
-        let folder_with_tilde = folder_str.replace(&std::env::var("HOME").unwrap_or_default(), "~");
-        let folder_line = format!("folder = {}", folder_with_tilde);
+    let wallpaper_path = tilde_path(wallpaper);
 
-        if let Some(pos) = lines.iter().position(|line| line.starts_with("folder = ")) {
-            lines[pos] = folder_line;
-        } else {
-            lines.push(folder_line);
+    let rt = Runtime::new().expect("Failed to create Tokio runtime");
+    rt.block_on(async {
+        let current_backend = *CURRENT_BACKEND.lock();
+        if current_backend == WallpaperBackend::None {
+            eprintln!(
+                "No wallpaper backend set. Please set a backend using the -b or --backend option."
+            );
+            return;
         }
 
-        let new_contents = lines.join("\n");
+        let previous_backend = current_backend;
+        drop_all_wallpapers(previous_backend).await;
+        kill_previous_backend(previous_backend).await;
 
-        if let Ok(mut file) = OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(&config_path)
-        {
-            let _ = writeln!(file, "{}", new_contents);
+        match set_wallpaper_internal(&wallpaper_path).await {
+            Ok(_) => {
+                println!("Wallpaper set successfully: {}", wallpaper_path);
+                wallpaper_changed(&wallpaper_path);
+            }
+            Err(e) => eprintln!("Error setting wallpaper: {}", e),
         }
-
-        println!("Wallpaper folder set to: {}", folder_with_tilde);
-    } else {
-        eprintln!("Specified folder does not exist or is not a directory.");
-    }
+    });
+    rt.block_on(hooks::wait());
 }
 
-fn set_random_wallpaper() {
+fn set_random_wallpaper(tag: Option<&str>) {
     let rt = Runtime::new().expect("Failed to create Tokio runtime");
     rt.block_on(async {
-        match get_random_wallpaper().await {
+        match get_random_wallpaper(tag).await {
             Ok(path) => match set_wallpaper_internal(&path).await {
                 Ok(_) => {
                     println!("Random wallpaper set successfully: {}", path);
-                    gui::save_last_wallpaper(&path);
+                    wallpaper_changed(&path);
                 }
                 Err(e) => eprintln!("Error setting random wallpaper: {}", e),
             },
             Err(e) => eprintln!("Error getting random wallpaper: {}", e),
         }
     });
+    rt.block_on(hooks::wait());
 }
 
-async fn get_random_wallpaper() -> Result<String, String> {
-    let config_path = shellexpand::tilde("~/.config/hyprwall/config.ini").into_owned();
-    let contents = tokio::fs::read_to_string(&config_path)
-        .await
-        .map_err(|e| format!("Failed to read config file: {}", e))?;
-
-    let folder = contents
-        .lines()
-        .find(|line| line.starts_with("folder = "))
-        .map(|line| line.trim_start_matches("folder = "))
-        .ok_or_else(|| "Wallpaper folder not found in config".to_string())?;
+async fn get_wallpapers() -> Result<Vec<String>, String> {
+    let folder =
+        gui::load_last_path().ok_or_else(|| "Wallpaper folder not found in config".to_string())?;
 
-    let folder_path = PathBuf::from(shellexpand::tilde(folder).into_owned());
+    get_wallpapers_in(&folder).await
+}
 
-    let mut entries = tokio::fs::read_dir(&folder_path)
+async fn get_wallpapers_in(folder_path: &Path) -> Result<Vec<String>, String> {
+    let mut entries = tokio::fs::read_dir(folder_path)
         .await
         .map_err(|e| format!("Failed to read wallpaper directory: {}", e))?;
 
@@ -303,23 +603,479 @@ async fn get_random_wallpaper() -> Result<String, String> {
 
     while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
         let path = entry.path();
-        if path.is_file()
-            && matches!(
-                path.extension().and_then(|e| e.to_str()),
-                Some("png" | "jpg" | "jpeg")
-            )
-        {
-            let path_str = path
-                .to_string_lossy()
-                .replace(&std::env::var("HOME").unwrap_or_default(), "~");
-            wallpapers.push(path_str);
+        if formats::is_wallpaper(&path) {
+            wallpapers.push(tilde_path(&path));
         }
     }
 
-    wallpapers
-        .choose(&mut rand::thread_rng())
+    wallpapers.sort();
+    Ok(wallpapers)
+}
+
+async fn sort_wallpapers(
+    mut wallpapers: Vec<String>,
+    order: sort::SortOrder,
+) -> Result<Vec<String>, String> {
+    run_blocking(move || {
+        wallpapers.sort_by_cached_key(|path| {
+            (
+                sort::key(order, Path::new(&expand_path(path))),
+                path.clone(),
+            )
+        });
+        Ok(wallpapers)
+    })
+    .await
+}
+
+async fn get_tagged_wallpapers(tag: Option<&str>) -> Result<Vec<String>, String> {
+    let mut wallpapers = get_wallpapers().await?;
+    if let Some(tag) = tag {
+        let library = library::load();
+        wallpapers.retain(|path| library.has_tag(path, tag));
+    }
+    Ok(wallpapers)
+}
+
+pub async fn get_random_wallpaper(tag: Option<&str>) -> Result<String, String> {
+    let folder =
+        gui::load_last_path().ok_or_else(|| "Wallpaper folder not found in config".to_string())?;
+    let wallpapers = get_tagged_wallpapers(tag).await?;
+    shuffle::next(&shuffle::source(&folder, tag), &wallpapers)
         .ok_or_else(|| "No wallpapers found".to_string())
-        .map(|p| p.to_string())
+}
+
+pub async fn get_random_favorite() -> Result<String, String> {
+    let folder =
+        gui::load_last_path().ok_or_else(|| "Wallpaper folder not found in config".to_string())?;
+    let mut wallpapers = get_wallpapers().await?;
+    let library = library::load();
+    wallpapers.retain(|path| library.is_favorite(path));
+    shuffle::next(&shuffle::favorites_source(&folder), &wallpapers)
+        .ok_or_else(|| "No favorite wallpapers found".to_string())
+}
+
+fn expand_path(path: &str) -> String {
+    shellexpand::tilde(path).into_owned()
+}
+
+fn print_json(value: &impl Serialize) {
+    match serde_json::to_string_pretty(value) {
+        Ok(json) => println!("{}", json),
+        Err(e) => eprintln!("Error serializing JSON: {}", e),
+    }
+}
+
+fn wallpaper_info(path: &str, library: &library::Library) -> WallpaperInfo {
+    let expanded = expand_path(path);
+    let metadata = fs::metadata(&expanded).ok();
+    let info = Pixbuf::file_info(&expanded);
+
+    WallpaperInfo {
+        name: Path::new(&expanded)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        format: info
+            .as_ref()
+            .and_then(|(format, _, _)| format.name())
+            .map(String::from),
+        width: info.as_ref().map(|(_, width, _)| *width),
+        height: info.as_ref().map(|(_, _, height)| *height),
+        size: metadata.as_ref().map_or(0, |m| m.len()),
+        modified: metadata
+            .and_then(|m| m.modified().ok())
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs()),
+        favorite: library.is_favorite(path),
+        tags: library.tags(path).to_vec(),
+        path: expanded,
+    }
+}
+
+fn list_wallpapers(tag: Option<&str>, json: bool) {
+    let rt = Runtime::new().expect("Failed to create Tokio runtime");
+    let wallpapers = rt.block_on(async {
+        sort_wallpapers(get_tagged_wallpapers(tag).await?, sort::SortOrder::load()).await
+    });
+    match wallpapers {
+        Ok(wallpapers) if json => {
+            let library = library::load();
+            let infos: Vec<WallpaperInfo> = wallpapers
+                .iter()
+                .map(|w| wallpaper_info(w, &library))
+                .collect();
+            print_json(&infos);
+        }
+        Ok(wallpapers) => {
+            for wallpaper in wallpapers {
+                println!("{}", wallpaper);
+            }
+        }
+        Err(e) => eprintln!("Error listing wallpapers: {}", e),
+    }
+}
+
+async fn get_active_wallpapers(backend: WallpaperBackend) -> Vec<MonitorStatus> {
+    let query = match backend {
+        WallpaperBackend::Hyprpaper => Some(("hyprctl", vec!["hyprpaper", "listactive"])),
+        WallpaperBackend::Swww => Some(("swww", vec!["query"])),
+        _ => None,
+    };
+
+    if let Some((program, args)) = query {
+        if let Ok(output) = TokioCommand::new(program).args(args).output().await {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let monitors: Vec<MonitorStatus> = stdout
+                .lines()
+                .filter_map(|line| match backend {
+                    WallpaperBackend::Hyprpaper => {
+                        line.split_once(" = ").map(|(monitor, path)| MonitorStatus {
+                            name: monitor.trim().to_string(),
+                            wallpaper: Some(path.trim().to_string()),
+                        })
+                    }
+                    _ => {
+                        let (monitor, rest) = line.trim_start_matches(": ").split_once(':')?;
+                        Some(MonitorStatus {
+                            name: monitor.trim().to_string(),
+                            wallpaper: rest
+                                .split_once("image: ")
+                                .map(|(_, path)| path.trim().to_string()),
+                        })
+                    }
+                })
+                .collect();
+
+            if !monitors.is_empty() {
+                return monitors;
+            }
+        }
+    }
+
+    let last_wallpaper = gui::load_last_wallpaper().map(|w| expand_path(&w));
+    get_monitors()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|monitor| MonitorStatus {
+            name: monitor.name,
+            wallpaper: last_wallpaper.clone(),
+        })
+        .collect()
+}
+
+fn print_status(json: bool) {
+    let rt = Runtime::new().expect("Failed to create Tokio runtime");
+    let backend = *CURRENT_BACKEND.lock();
+
+    let status = rt.block_on(async {
+        let daemon = match backend.daemon_process() {
+            Some(process) => Some(DaemonStatus {
+                process,
+                running: is_process_running(process).await,
+            }),
+            None => None,
+        };
+
+        Status {
+            backend: backend.name(),
+            daemon,
+            folder: gui::load_last_path().map(|p| p.to_string_lossy().into_owned()),
+            wallpaper: gui::load_last_wallpaper().map(|w| expand_path(&w)),
+            monitors: get_active_wallpapers(backend).await,
+        }
+    });
+
+    if json {
+        print_json(&status);
+        return;
+    }
+
+    let or_none = |value: &Option<String>| value.clone().unwrap_or_else(|| "none".to_string());
+
+    println!("Backend: {}", status.backend);
+    if let Some(daemon) = &status.daemon {
+        println!(
+            "Daemon: {} ({})",
+            daemon.process,
+            if daemon.running { "running" } else { "stopped" }
+        );
+    }
+    println!("Folder: {}", or_none(&status.folder));
+    println!("Wallpaper: {}", or_none(&status.wallpaper));
+    for monitor in &status.monitors {
+        println!("Monitor {}: {}", monitor.name, or_none(&monitor.wallpaper));
+    }
+}
+
+pub async fn get_step_wallpaper(step: isize, order: sort::SortOrder) -> Result<String, String> {
+    let wallpapers = sort_wallpapers(get_wallpapers().await?, order).await?;
+    if wallpapers.is_empty() {
+        return Err("No wallpapers found".to_string());
+    }
+
+    let len = wallpapers.len() as isize;
+    let index = state::get("cursor")
+        .filter(|cursor| cursor != "none")
+        .or_else(gui::load_last_wallpaper)
+        .and_then(|current| wallpapers.iter().position(|w| *w == current))
+        .map_or(if step > 0 { 0 } else { len - 1 }, |i| {
+            (i as isize + step).rem_euclid(len)
+        });
+
+    Ok(wallpapers[index as usize].clone())
+}
+
+pub fn save_cursor(path: &str) {
+    state::set("cursor", path);
+}
+
+fn step_wallpaper(step: isize) {
+    let rt = Runtime::new().expect("Failed to create Tokio runtime");
+    rt.block_on(async {
+        let path = match get_step_wallpaper(step, sort::SortOrder::load()).await {
+            Ok(path) => path,
+            Err(e) => {
+                eprintln!("Error getting wallpaper: {}", e);
+                return;
+            }
+        };
+
+        match set_wallpaper_internal(&path).await {
+            Ok(_) => {
+                println!("Wallpaper set successfully: {}", path);
+                wallpaper_changed(&path);
+                save_cursor(&path);
+            }
+            Err(e) => eprintln!("Error setting wallpaper: {}", e),
+        }
+    });
+    rt.block_on(hooks::wait());
+}
+
+fn print_palette(path: Option<PathBuf>, json: bool) {
+    let Some(path) = path
+        .map(|path| path.to_string_lossy().into_owned())
+        .or_else(gui::load_last_wallpaper)
+    else {
+        eprintln!("No wallpaper set");
+        return;
+    };
+
+    let palette =
+        match slideshow::resolve(&expand_path(&path)).and_then(|path| palette::extract(&path)) {
+            Ok(palette) => palette,
+            Err(e) => {
+                eprintln!("Error extracting palette: {}", e);
+                return;
+            }
+        };
+
+    if json {
+        print_json(&palette.to_json());
+        return;
+    }
+
+    println!("background  {}", palette::hex(palette.background()));
+    println!("foreground  {}", palette::hex(palette.foreground()));
+    for (i, color) in palette.colors.iter().enumerate() {
+        println!("color{:<6}{}", i, palette::hex(*color));
+    }
+}
+
+fn print_history(json: bool) {
+    let entries = history::load();
+
+    if json {
+        print_json(&entries);
+        return;
+    }
+
+    for entry in entries {
+        let time = chrono::Local
+            .timestamp_opt(entry.timestamp as i64, 0)
+            .single()
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_else(|| entry.timestamp.to_string());
+        println!(
+            "{}  {}  {}  {}",
+            time, entry.backend, entry.monitor, entry.path
+        );
+    }
+}
+
+fn undo_wallpaper() {
+    let Some(entry) = history::previous() else {
+        eprintln!("No previous wallpaper found in history");
+        return;
+    };
+
+    let rt = Runtime::new().expect("Failed to create Tokio runtime");
+    match rt.block_on(set_wallpapers(&entry.path, &entry.monitors)) {
+        Ok(_) => {
+            history::pop();
+            println!("Wallpaper set successfully: {}", entry.path);
+            wallpapers_changed(&entry.path, &entry.monitors);
+        }
+        Err(e) => eprintln!("Error setting wallpaper: {}", e),
+    }
+    rt.block_on(hooks::wait());
+}
+
+fn use_profile(name: &str) {
+    let rt = Runtime::new().expect("Failed to create Tokio runtime");
+    match rt.block_on(profile::apply(name)) {
+        Ok(_) => println!("Profile applied: {}", name),
+        Err(e) => eprintln!("Error applying profile: {}", e),
+    }
+    rt.block_on(hooks::wait());
+}
+
+fn toggle_favorite(path: &Path) {
+    let path = tilde_path(path);
+    if library::update(|library| library.toggle_favorite(&path)) {
+        println!("Added to favorites: {}", path);
+    } else {
+        println!("Removed from favorites: {}", path);
+    }
+}
+
+fn set_focal_point(path: &Path, point: &str) {
+    let path = tilde_path(path);
+    let focal_point = if point == "reset" {
+        None
+    } else {
+        match render::parse_focal_point(point) {
+            Some(focal_point) => Some(focal_point),
+            None => {
+                eprintln!(
+                    "Invalid focal point specified. Use two numbers from 0 to 1, e.g. 0.5,0.3."
+                );
+                return;
+            }
+        }
+    };
+
+    library::update(|library| library.set_focal_point(&path, focal_point));
+    println!("Focal point of {} set to: {}", path, point);
+}
+
+fn set_effects(path: &Path, spec: &str, reset: bool) {
+    let path = tilde_path(path);
+    if reset {
+        library::update(|library| library.set_effects(&path, None));
+        println!("Effects of {} reset to: {}", path, effects::global());
+        return;
+    }
+
+    if spec.is_empty() {
+        println!("{}", effects::for_wallpaper(&path));
+        return;
+    }
+
+    match effects::Effects::parse(spec) {
+        Ok(effects) => {
+            library::update(|library| library.set_effects(&path, Some(effects.to_string())));
+            println!("Effects of {} set to: {}", path, effects);
+        }
+        Err(e) => eprintln!("{}", e),
+    }
+}
+
+fn add_tags(path: &Path, tags: &[String]) {
+    let path = tilde_path(path);
+    let tags = library::update(|library| {
+        library.add_tags(&path, tags);
+        library.tags(&path).join(", ")
+    });
+    println!("Tags of {}: {}", path, tags);
+}
+
+fn remove_tags(path: &Path, tags: &[String]) {
+    let path = tilde_path(path);
+    let tags = library::update(|library| {
+        library.remove_tags(&path, tags);
+        library.tags(&path).join(", ")
+    });
+    println!("Tags of {}: {}", path, tags);
+}
+
+fn list_tags(path: Option<&Path>) {
+    let library = library::load();
+    let tags: Vec<String> = match path {
+        Some(path) => library.tags(&tilde_path(path)).to_vec(),
+        None => library.all_tags().into_iter().collect(),
+    };
+    for tag in tags {
+        println!("{}", tag);
+    }
+}
+
+fn config_get(key: &str) {
+    match config::get(key) {
+        Some(value) => println!("{}", value),
+        None => eprintln!("Config key not found: {}", key),
+    }
+}
+
+pub fn validate_setting(key: &str, value: &str) -> Result<(), String> {
+    let error = match key {
+        "backend" if WallpaperBackend::from_name(value).is_none() => {
+            "Invalid backend specified. Use one of: none, hyprpaper, swaybg, swww, wallutils, feh."
+        }
+        "folder" if !Path::new(&shellexpand::tilde(value).into_owned()).is_dir() => {
+            "Specified folder does not exist or is not a directory."
+        }
+        "fit" if fit::FitMode::from_name(value).is_none() => {
+            "Invalid fit mode specified. Use one of: fill, fit, center, tile."
+        }
+        "sort" if sort::SortOrder::from_name(value).is_none() => {
+            "Invalid sort order specified. Use one of: name, natural, mtime, size, resolution, aspect, color."
+        }
+        "cache_size" if value.parse::<u64>().is_err() => {
+            "Invalid cache size specified. Use a number of megabytes."
+        }
+        "span_gap" if value.parse::<i32>().is_err() => {
+            "Invalid span gap specified. Use a number of pixels."
+        }
+        "effects" => return effects::Effects::parse(value).map(|_| ()),
+        "focal_point" if render::parse_focal_point(value).is_none() => {
+            "Invalid focal point specified. Use two numbers from 0 to 1, e.g. 0.5,0.3."
+        }
+        "lockscreen"
+            if value
+                .split(',')
+                .map(str::trim)
+                .any(|target| !["hyprlock", "swaylock", "symlink"].contains(&target)) =>
+        {
+            "Invalid lock screen specified. Use any of: hyprlock, swaylock, symlink."
+        }
+        "random_weight" if shuffle::Weighting::from_name(value).is_none() => {
+            "Invalid random weight specified. Use one of: none, favorites, recency."
+        }
+        _ => return Ok(()),
+    };
+    Err(error.to_string())
+}
+
+fn config_set(key: &str, value: &str) {
+    match key {
+        "backend" => set_backend(value),
+        "folder" => set_folder(&absolute_path(PathBuf::from(value))),
+        _ => match validate_setting(key, value) {
+            Err(e) => eprintln!("{}", e),
+            Ok(_) if key == "effects" => {
+                let effects = effects::Effects::parse(value).unwrap_or_default();
+                config::set(key, &effects.to_string());
+                println!("{} set to: {}", key, effects);
+            }
+            Ok(_) => {
+                config::set(key, value);
+                println!("{} set to: {}", key, value);
+            }
+        },
+    }
 }
 
 pub fn set_wallpaper(path: String) {
@@ -328,7 +1084,8 @@ pub fn set_wallpaper(path: String) {
         match set_wallpaper_internal(&path).await {
             Ok(_) => {
                 println!("Wallpaper set successfully: {}", path);
-                gui::save_last_wallpaper(&path);
+                wallpaper_changed(&path);
+                gui::refresh_recent();
             }
             Err(e) => {
                 eprintln!("Error setting wallpaper: {}", e);
@@ -338,30 +1095,216 @@ pub fn set_wallpaper(path: String) {
     });
 }
 
+async fn apply_wallpaper(path: &str) -> Result<(), String> {
+    set_wallpaper_internal(path).await?;
+    wallpaper_changed(path);
+    Ok(())
+}
+
+fn wallpaper_changed(path: &str) {
+    gui::save_last_wallpaper(path);
+    history::record(path, &[], *CURRENT_BACKEND.lock());
+}
+
+pub fn monitor_wallpapers_changed(wallpapers: &[(String, String)]) {
+    gui::save_last_monitor_wallpapers(wallpapers);
+    history::record(&wallpapers[0].1, wallpapers, *CURRENT_BACKEND.lock());
+}
+
+async fn set_wallpapers(path: &str, monitors: &[(String, String)]) -> Result<(), String> {
+    if monitors.is_empty() {
+        set_wallpaper_internal(path).await
+    } else {
+        set_monitor_wallpapers(monitors).await
+    }
+}
+
+fn wallpapers_changed(path: &str, monitors: &[(String, String)]) {
+    if monitors.is_empty() {
+        wallpaper_changed(path);
+    } else {
+        monitor_wallpapers_changed(monitors);
+    }
+}
+
 async fn set_wallpaper_internal(path: &str) -> Result<(), String> {
-    let path = shellexpand::tilde(path).into_owned();
+    let source = shellexpand::tilde(path).into_owned();
+    if slideshow::is_slideshow(Path::new(&source)) && !is_daemon_running().await {
+        eprintln!("Warning: slideshows only advance while `hyprwall daemon` is running");
+    }
+    let path = slideshow::resolve(&source)?;
+    change_wallpaper(&path, &[(None, source)]).await
+}
+
+pub async fn set_monitor_wallpapers(wallpapers: &[(String, String)]) -> Result<(), String> {
+    let backend = *CURRENT_BACKEND.lock();
+    if !backend.supports_monitors() {
+        return Err(format!(
+            "{} can't set a different wallpaper per monitor",
+            backend.name()
+        ));
+    }
+
+    let targets = wallpapers
+        .iter()
+        .map(|(monitor, path)| (Some(monitor.clone()), shellexpand::tilde(path).into_owned()))
+        .collect::<Vec<_>>();
+    let Some((_, first)) = targets.first() else {
+        return Err("No wallpapers given".to_string());
+    };
+    let first = slideshow::resolve(first)?;
+
+    change_wallpaper(&first, &targets).await
+}
+
+async fn change_wallpaper(path: &str, targets: &[(Option<String>, String)]) -> Result<(), String> {
     let current_backend = *CURRENT_BACKEND.lock();
+    let mut context = hooks::HookContext::new(path, current_backend);
+
+    let result = apply_assignments(targets, current_backend, &mut context).await;
+
+    match &result {
+        Ok(assignments) => {
+            gui::save_wallpaper_backend(&current_backend);
+            state::update(path, &per_monitor(assignments).await, current_backend);
+            let image = assignments[0].path.clone();
+            let _ = tokio::task::spawn_blocking(move || palette::export(&image)).await;
+            sync_lockscreen(targets, current_backend).await;
+            hooks::run(hooks::HookEvent::Post, &context).await;
+        }
+        Err(e) => {
+            context.error = Some(e.clone());
+            hooks::run(hooks::HookEvent::Failure, &context).await;
+        }
+    }
+
+    result.map(|_| ())
+}
+
+async fn sync_lockscreen(targets: &[(Option<String>, String)], backend: WallpaperBackend) {
+    if !lockscreen::enabled() {
+        return;
+    }
+
+    let targets = targets.to_vec();
+    let result = run_blocking(move || {
+        let images = targets
+            .into_iter()
+            .map(|(monitor, source)| {
+                let path = formats::prepare_for_backend(&slideshow::resolve(&source)?, backend)?;
+                let effects = effects::for_wallpaper(&tilde_path(Path::new(&source)));
+                Ok((monitor, effects::apply(&path, &effects)?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        lockscreen::sync(&images);
+        Ok(())
+    })
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Error updating lock screen: {}", e);
+    }
+}
+
+async fn per_monitor(assignments: &[Assignment]) -> Vec<Assignment> {
+    if assignments
+        .iter()
+        .all(|assignment| assignment.monitor.is_some())
+    {
+        return assignments.to_vec();
+    }
+
+    let monitors: Vec<String> = get_monitors()
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|monitor| monitor.name)
+        .filter(|name| {
+            !assignments
+                .iter()
+                .any(|assignment| assignment.monitor.as_ref() == Some(name))
+        })
+        .collect();
+    if monitors.is_empty() {
+        return assignments.to_vec();
+    }
 
+    assignments
+        .iter()
+        .flat_map(|assignment| match assignment.monitor {
+            Some(_) => vec![assignment.clone()],
+            None => monitors
+                .iter()
+                .map(|monitor| Assignment {
+                    monitor: Some(monitor.clone()),
+                    path: assignment.path.clone(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+async fn apply_assignments(
+    targets: &[(Option<String>, String)],
+    current_backend: WallpaperBackend,
+    context: &mut hooks::HookContext,
+) -> Result<Vec<Assignment>, String> {
     kill_other_backends(current_backend).await;
 
     ensure_backend_running().await?;
 
-    println!("Attempting to set wallpaper: {}", path);
+    let mut assignments = Vec::new();
+    for (monitor, source) in targets {
+        let path = &slideshow::resolve(source)?;
+        println!("Attempting to set wallpaper: {}", path);
+
+        let rendered = match monitor {
+            Some(monitor) => {
+                let source = path.clone();
+                vec![Assignment {
+                    monitor: Some(monitor.clone()),
+                    path: run_blocking(move || {
+                        formats::prepare_for_backend(&source, current_backend)
+                    })
+                    .await?,
+                }]
+            }
+            None => get_assignments(path, current_backend).await?,
+        };
+
+        let effects = effects::for_wallpaper(&tilde_path(Path::new(source)));
+        let processed = run_blocking(move || {
+            rendered
+                .into_iter()
+                .map(|assignment| {
+                    Ok(Assignment {
+                        path: effects::apply(&assignment.path, &effects)?,
+                        ..assignment
+                    })
+                })
+                .collect::<Result<Vec<_>, String>>()
+        })
+        .await?;
+        assignments.extend(processed);
+    }
 
-    let result = match current_backend {
-        WallpaperBackend::Hyprpaper => set_hyprpaper_wallpaper(&path).await,
-        WallpaperBackend::Swaybg => set_swaybg_wallpaper(&path).await,
-        WallpaperBackend::Swww => set_swww_wallpaper(&path).await,
-        WallpaperBackend::Wallutils => set_wallutils_wallpaper(&path).await,
-        WallpaperBackend::Feh => set_feh_wallpaper(&path).await,
+    context.image = Some(assignments[0].path.clone());
+    context.monitors = assignments
+        .iter()
+        .filter_map(|assignment| assignment.monitor.clone())
+        .collect();
+    hooks::run(hooks::HookEvent::Pre, context).await;
+
+    match current_backend {
+        WallpaperBackend::Hyprpaper => set_hyprpaper_wallpaper(&assignments).await,
+        WallpaperBackend::Swaybg => set_swaybg_wallpaper(&assignments).await,
+        WallpaperBackend::Swww => set_swww_wallpaper(&assignments).await,
+        WallpaperBackend::Wallutils => set_wallutils_wallpaper(&assignments[0].path).await,
+        WallpaperBackend::Feh => set_feh_wallpaper(&assignments[0].path).await,
         WallpaperBackend::None => Err("No wallpaper backend set".to_string()),
-    };
-
-    if result.is_ok() {
-        gui::save_wallpaper_backend(&current_backend);
-    }
+    }?;
 
-    result
+    Ok(assignments)
 }
 
 async fn kill_other_backends(current_backend: WallpaperBackend) {
@@ -381,9 +1324,15 @@ async fn kill_other_backends(current_backend: WallpaperBackend) {
     }
 }
 
-async fn set_hyprpaper_wallpaper(path: &str) -> Result<(), String> {
-    let preload_command = format!("hyprctl hyprpaper preload \"{}\"", path);
-    spawn_background_process(&preload_command).await?;
+async fn set_hyprpaper_wallpaper(assignments: &[Assignment]) -> Result<(), String> {
+    let mut preloaded: Vec<&str> = Vec::new();
+    for assignment in assignments {
+        if !preloaded.contains(&assignment.path.as_str()) {
+            let preload_command = format!("hyprctl hyprpaper preload \"{}\"", assignment.path);
+            spawn_background_process(&preload_command).await?;
+            preloaded.push(&assignment.path);
+        }
+    }
 
     let monitors = get_monitors().await?;
 
@@ -393,16 +1342,35 @@ async fn set_hyprpaper_wallpaper(path: &str) -> Result<(), String> {
 
     *MONITORS.lock() = monitors.clone();
 
-    for monitor in monitors {
-        let set_command = format!("hyprctl hyprpaper wallpaper \"{},{}\"", monitor, path);
-        spawn_background_process(&set_command).await?;
+    let mode = fit::FitMode::load().backend_mode(WallpaperBackend::Hyprpaper);
+
+    for assignment in assignments {
+        let targets: Vec<&str> = match &assignment.monitor {
+            Some(monitor) => vec![monitor.as_str()],
+            None => monitors.iter().map(|m| m.name.as_str()).collect(),
+        };
+        for monitor in targets {
+            let set_command = format!(
+                "hyprctl hyprpaper wallpaper \"{},{}{}\"",
+                monitor, mode, assignment.path
+            );
+            spawn_background_process(&set_command).await?;
+        }
     }
 
     Ok(())
 }
 
-async fn set_swaybg_wallpaper(path: &str) -> Result<(), String> {
-    let command = format!("swaybg -i \"{}\" -m fill &", path);
+async fn set_swaybg_wallpaper(assignments: &[Assignment]) -> Result<(), String> {
+    let mode = fit::FitMode::load().backend_mode(WallpaperBackend::Swaybg);
+    let outputs: Vec<String> = assignments
+        .iter()
+        .map(|assignment| match &assignment.monitor {
+            Some(monitor) => format!("-o \"{}\" -i \"{}\" -m {}", monitor, assignment.path, mode),
+            None => format!("-i \"{}\" -m {}", assignment.path, mode),
+        })
+        .collect();
+    let command = format!("swaybg {} &", outputs.join(" "));
     TokioCommand::new("sh")
         .arg("-c")
         .arg(&command)
@@ -417,9 +1385,20 @@ async fn set_swaybg_wallpaper(path: &str) -> Result<(), String> {
     }
 }
 
-async fn set_swww_wallpaper(path: &str) -> Result<(), String> {
-    let command = format!("swww img \"{}\"", path);
-    spawn_background_process(&command).await
+async fn set_swww_wallpaper(assignments: &[Assignment]) -> Result<(), String> {
+    let resize = fit::FitMode::load().backend_mode(WallpaperBackend::Swww);
+
+    for assignment in assignments {
+        let command = match &assignment.monitor {
+            Some(monitor) => format!(
+                "swww img --resize {} -o \"{}\" \"{}\"",
+                resize, monitor, assignment.path
+            ),
+            None => format!("swww img --resize {} \"{}\"", resize, assignment.path),
+        };
+        spawn_background_process(&command).await?;
+    }
+    Ok(())
 }
 
 async fn set_wallutils_wallpaper(path: &str) -> Result<(), String> {
@@ -428,7 +1407,8 @@ async fn set_wallutils_wallpaper(path: &str) -> Result<(), String> {
 }
 
 async fn set_feh_wallpaper(path: &str) -> Result<(), String> {
-    let command = format!("feh --bg-fill \"{}\"", path);
+    let mode = fit::FitMode::load().backend_mode(WallpaperBackend::Feh);
+    let command = format!("feh --bg-{} \"{}\"", mode, path);
     spawn_background_process(&command).await
 }
 
@@ -455,31 +1435,90 @@ async fn spawn_background_process(command: &str) -> Result<(), String> {
     Ok(())
 }
 
-async fn get_monitors() -> Result<Vec<String>, String> {
-    println!("Retrieving monitor information");
+async fn get_monitors() -> Result<Vec<Monitor>, String> {
+    eprintln!("Retrieving monitor information");
     let output = TokioCommand::new("hyprctl")
-        .arg("monitors")
+        .args(["monitors", "-j"])
         .output()
         .await
         .map_err(|e| format!("Failed to execute hyprctl monitors: {}", e))?;
 
-    let monitors: Vec<String> = String::from_utf8_lossy(&output.stdout)
-        .lines()
-        .filter_map(|line| {
-            if line.starts_with("Monitor ") {
-                let monitor_name = line.split_whitespace().nth(1).map(String::from);
-                println!("Found monitor: {:?}", monitor_name);
-                monitor_name
-            } else {
-                None
-            }
-        })
-        .collect();
+    let monitors: Vec<Monitor> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse hyprctl monitors output: {}", e))?;
 
-    println!("Retrieved monitors: {:?}", monitors);
+    eprintln!(
+        "Retrieved monitors: {:?}",
+        monitors.iter().map(|m| &m.name).collect::<Vec<_>>()
+    );
     Ok(monitors)
 }
 
+async fn get_assignments(path: &str, backend: WallpaperBackend) -> Result<Vec<Assignment>, String> {
+    let per_monitor = backend.supports_monitors();
+    let is_gif = formats::sniff(Path::new(path)) == Some(formats::ImageFormat::Gif);
+
+    let source = path.to_string();
+
+    if per_monitor && !is_gif && render::span_enabled() {
+        let monitors = render_monitors("span").await;
+        if !monitors.is_empty() {
+            let slices = run_blocking(move || render::span(&source, &monitors)).await?;
+            return Ok(slices
+                .into_iter()
+                .map(|(monitor, path)| Assignment {
+                    monitor: Some(monitor),
+                    path,
+                })
+                .collect());
+        }
+    }
+
+    if per_monitor && !is_gif && render::prescale_enabled() {
+        let monitors = render_monitors("prescale").await;
+        if !monitors.is_empty() {
+            return run_blocking(move || {
+                monitors
+                    .iter()
+                    .map(|monitor| {
+                        Ok(Assignment {
+                            monitor: Some(monitor.name.clone()),
+                            path: render::rendition(&source, monitor)?,
+                        })
+                    })
+                    .collect()
+            })
+            .await;
+        }
+    }
+
+    Ok(vec![Assignment {
+        monitor: None,
+        path: run_blocking(move || formats::prepare_for_backend(&source, backend)).await?,
+    }])
+}
+
+async fn render_monitors(option: &str) -> Vec<Monitor> {
+    match get_monitors().await {
+        Ok(monitors) if !monitors.is_empty() => monitors,
+        Ok(_) => {
+            eprintln!("No monitors found, ignoring {}", option);
+            Vec::new()
+        }
+        Err(e) => {
+            eprintln!("{}, ignoring {} (it needs Hyprland)", e, option);
+            Vec::new()
+        }
+    }
+}
+
+async fn run_blocking<T: Send + 'static>(
+    task: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(task)
+        .await
+        .map_err(|_| "Image processing panicked".to_string())?
+}
+
 async fn ensure_backend_running() -> Result<(), String> {
     let backend = *CURRENT_BACKEND.lock();
     match backend {
@@ -537,6 +1576,16 @@ async fn is_process_running(process_name: &str) -> bool {
         .unwrap_or(false)
 }
 
+async fn is_daemon_running() -> bool {
+    TokioCommand::new("pgrep")
+        .arg("-f")
+        .arg("hyprwall daemon")
+        .status()
+        .await
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
 async fn start_process(command: &str) -> Result<(), String> {
     TokioCommand::new("sh")
         .arg("-c")
@@ -568,13 +1617,8 @@ pub fn set_wallpaper_backend(backend: WallpaperBackend) {
 }
 
 async fn kill_previous_backend(backend: WallpaperBackend) {
-    let process_name = match backend {
-        WallpaperBackend::Hyprpaper => "hyprpaper",
-        WallpaperBackend::Swaybg => "swaybg",
-        WallpaperBackend::Swww => "swww-daemon",
-        WallpaperBackend::Wallutils => return,
-        WallpaperBackend::Feh => return,
-        WallpaperBackend::None => return,
+    let Some(process_name) = backend.daemon_process() else {
+        return;
     };
 
     let _ = TokioCommand::new("killall")
@@ -600,16 +1644,18 @@ async fn drop_all_wallpapers(backend: WallpaperBackend) {
 
 fn restore_last_wallpaper() {
     if let Some(last_wallpaper) = gui::load_last_wallpaper() {
+        let monitors = gui::load_last_monitor_wallpapers();
         let rt = Runtime::new().expect("Failed to create Tokio runtime");
-        match rt.block_on(set_wallpaper_internal(&last_wallpaper)) {
+        match rt.block_on(set_wallpapers(&last_wallpaper, &monitors)) {
             Ok(_) => {
                 println!("Wallpaper restored successfully");
-                gui::save_last_wallpaper(&last_wallpaper);
+                wallpapers_changed(&last_wallpaper, &monitors);
             }
             Err(e) => {
                 eprintln!("Error restoring wallpaper: {}", e);
             }
         }
+        rt.block_on(hooks::wait());
     } else {
         eprintln!("No last wallpaper found to restore");
     }
@@ -0,0 +1,285 @@
+use gtk::gdk_pixbuf::Pixbuf;
+use serde_json::json;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::{atomic, cache, config};
+
+const PALETTE_SECTION: &str = "Palette";
+const SAMPLE_SIZE: i32 = 128;
+const BASE_COLORS: usize = 8;
+const BRIGHT_AMOUNT: f64 = 0.25;
+const DOMINANT_BITS: u8 = 4;
+
+pub type Rgb = [u8; 3];
+
+#[derive(Clone, Debug)]
+pub struct Palette {
+    pub wallpaper: String,
+    pub colors: Vec<Rgb>,
+}
+
+pub fn hex(color: Rgb) -> String {
+    format!("#{:02x}{:02x}{:02x}", color[0], color[1], color[2])
+}
+
+fn parse_hex(value: &str) -> Option<Rgb> {
+    let hex = value.trim().trim_start_matches('#');
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some([channel(0)?, channel(2)?, channel(4)?])
+}
+
+fn luma(color: &Rgb) -> f64 {
+    0.2126 * color[0] as f64 + 0.7152 * color[1] as f64 + 0.0722 * color[2] as f64
+}
+
+fn brighten(color: Rgb) -> Rgb {
+    color.map(|c| (c as f64 + (255.0 - c as f64) * BRIGHT_AMOUNT).round() as u8)
+}
+
+fn channel_range(pixels: &[Rgb], channel: usize) -> u8 {
+    let (min, max) = pixels.iter().fold((u8::MAX, u8::MIN), |(min, max), p| {
+        (min.min(p[channel]), max.max(p[channel]))
+    });
+    max.saturating_sub(min)
+}
+
+fn average(pixels: &[Rgb]) -> Rgb {
+    let mut sum = [0u64; 3];
+    for pixel in pixels {
+        for (total, value) in sum.iter_mut().zip(pixel) {
+            *total += *value as u64;
+        }
+    }
+    sum.map(|total| (total / pixels.len().max(1) as u64) as u8)
+}
+
+fn median_cut(pixels: Vec<Rgb>, count: usize) -> Vec<Rgb> {
+    let mut boxes = vec![pixels];
+
+    while boxes.len() < count {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, pixels)| pixels.len() > 1)
+            .map(|(i, pixels)| {
+                let channel = (0..3)
+                    .max_by_key(|&c| channel_range(pixels, c))
+                    .unwrap_or(0);
+                (i, channel, channel_range(pixels, channel))
+            })
+            .filter(|(_, _, range)| *range > 0)
+            .max_by_key(|(_, _, range)| *range);
+
+        let Some((index, channel, _)) = widest else {
+            break;
+        };
+
+        let mut pixels = boxes.swap_remove(index);
+        pixels.sort_unstable_by_key(|p| p[channel]);
+        let upper = pixels.split_off(pixels.len() / 2);
+        boxes.push(pixels);
+        boxes.push(upper);
+    }
+
+    boxes
+        .iter()
+        .filter(|pixels| !pixels.is_empty())
+        .map(|pixels| average(pixels))
+        .collect()
+}
+
+fn sample_pixels(path: &str) -> Result<Vec<Rgb>, String> {
+    let pixbuf = Pixbuf::from_file_at_scale(path, SAMPLE_SIZE, SAMPLE_SIZE, true)
+        .map_err(|e| format!("Failed to load {}: {}", path, e))?;
+
+    let channels = pixbuf.n_channels() as usize;
+    let rowstride = pixbuf.rowstride() as usize;
+    let bytes = pixbuf.read_pixel_bytes();
+    let pixels: Vec<Rgb> = (0..pixbuf.height() as usize)
+        .flat_map(|y| {
+            let start = y * rowstride;
+            bytes[start..start + pixbuf.width() as usize * channels].chunks(channels)
+        })
+        .filter(|pixel| channels < 4 || pixel[3] >= 128)
+        .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+        .collect();
+
+    if pixels.is_empty() {
+        return Err(format!("No visible pixels in {}", path));
+    }
+    Ok(pixels)
+}
+
+pub fn dominant(path: &str) -> Result<Rgb, String> {
+    let key = format!("{}-dominant", cache::file_key(Path::new(path))?);
+    let cached = cache::get_or_create("palettes", &key, "txt", |target| {
+        let mut bins: HashMap<[u8; 3], (u64, [u64; 3])> = HashMap::new();
+        for pixel in sample_pixels(path)? {
+            let (count, sum) = bins
+                .entry(pixel.map(|c| c >> DOMINANT_BITS))
+                .or_insert((0, [0; 3]));
+            *count += 1;
+            for (total, value) in sum.iter_mut().zip(pixel) {
+                *total += value as u64;
+            }
+        }
+
+        let (count, sum) = bins
+            .into_values()
+            .max_by_key(|(count, _)| *count)
+            .unwrap_or_default();
+        let color = sum.map(|total| (total / count.max(1)) as u8);
+        fs::write(target, hex(color)).map_err(|e| format!("Failed to save palette: {}", e))
+    })?;
+
+    fs::read_to_string(&cached)
+        .ok()
+        .as_deref()
+        .and_then(parse_hex)
+        .ok_or_else(|| format!("Failed to read the dominant color of {}", path))
+}
+
+pub fn extract(path: &str) -> Result<Palette, String> {
+    let key = cache::content_hash(Path::new(path))?;
+    let cached = cache::get_or_create("palettes", &key, "txt", |target| {
+        let colors = median_cut(sample_pixels(path)?, BASE_COLORS);
+        let lines: Vec<String> = colors.into_iter().map(hex).collect();
+        fs::write(target, lines.join("\n")).map_err(|e| format!("Failed to save palette: {}", e))
+    })?;
+
+    let mut colors: Vec<Rgb> = fs::read_to_string(&cached)
+        .map_err(|e| format!("Failed to read palette: {}", e))?
+        .lines()
+        .filter_map(parse_hex)
+        .collect();
+    if colors.is_empty() {
+        return Err(format!("Failed to extract a palette from {}", path));
+    }
+
+    colors.sort_by(|a, b| luma(a).total_cmp(&luma(b)));
+    while colors.len() < BASE_COLORS {
+        colors.push(colors[colors.len() - 1]);
+    }
+    let bright: Vec<Rgb> = colors.iter().copied().map(brighten).collect();
+    colors.extend(bright);
+
+    Ok(Palette {
+        wallpaper: path.to_string(),
+        colors,
+    })
+}
+
+impl Palette {
+    pub fn background(&self) -> Rgb {
+        self.colors[0]
+    }
+
+    pub fn foreground(&self) -> Rgb {
+        self.colors[BASE_COLORS - 1]
+    }
+
+    fn named(&self) -> Vec<(String, Rgb)> {
+        [
+            ("background".to_string(), self.background()),
+            ("foreground".to_string(), self.foreground()),
+            ("cursor".to_string(), self.foreground()),
+        ]
+        .into_iter()
+        .chain(
+            self.colors
+                .iter()
+                .enumerate()
+                .map(|(i, color)| (format!("color{}", i), *color)),
+        )
+        .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let colors: serde_json::Map<String, serde_json::Value> = self
+            .colors
+            .iter()
+            .enumerate()
+            .map(|(i, color)| (format!("color{}", i), json!(hex(*color))))
+            .collect();
+
+        json!({
+            "wallpaper": self.wallpaper,
+            "special": {
+                "background": hex(self.background()),
+                "foreground": hex(self.foreground()),
+                "cursor": hex(self.foreground()),
+            },
+            "colors": colors,
+        })
+    }
+
+    pub fn render(&self, template: &str) -> Option<String> {
+        let named = self.named();
+        let lines: Vec<String> = match template {
+            "hyprland" => named
+                .iter()
+                .map(|(name, color)| format!("${} = rgb({})", name, &hex(*color)[1..]))
+                .collect(),
+            "waybar" | "css" => named
+                .iter()
+                .map(|(name, color)| format!("@define-color {} {};", name, hex(*color)))
+                .collect(),
+            "kitty" => named
+                .iter()
+                .map(|(name, color)| format!("{} {}", name, hex(*color)))
+                .collect(),
+            "foot" => std::iter::once("[colors]".to_string())
+                .chain([
+                    format!("background={}", &hex(self.background())[1..]),
+                    format!("foreground={}", &hex(self.foreground())[1..]),
+                ])
+                .chain(self.colors.iter().enumerate().map(|(i, color)| {
+                    let kind = if i < BASE_COLORS { "regular" } else { "bright" };
+                    format!("{}{}={}", kind, i % BASE_COLORS, &hex(*color)[1..])
+                }))
+                .collect(),
+            "json" => return serde_json::to_string_pretty(&self.to_json()).ok(),
+            _ => return None,
+        };
+
+        Some(lines.join("\n") + "\n")
+    }
+}
+
+fn write_template(palette: &Palette, template: &str, target: &str) -> Result<(), String> {
+    let contents = palette
+        .render(template)
+        .ok_or_else(|| format!("Unknown palette template: {}", template))?;
+    let target = shellexpand::tilde(target).into_owned();
+    atomic::write(Path::new(&target), contents.as_bytes())
+}
+
+pub fn export(path: &str) {
+    let outputs = config::section(PALETTE_SECTION);
+    if outputs.is_empty() {
+        return;
+    }
+
+    let palette = match extract(path) {
+        Ok(palette) => palette,
+        Err(e) => {
+            eprintln!("Error extracting palette: {}", e);
+            return;
+        }
+    };
+
+    for (template, target) in outputs.iter().filter(|(key, _)| key != "reload") {
+        if let Err(e) = write_template(&palette, template, target) {
+            eprintln!("Error exporting palette: {}", e);
+        }
+    }
+
+    for (_, command) in outputs.iter().filter(|(key, _)| key == "reload") {
+        if let Err(e) = Command::new("sh").arg("-c").arg(command).spawn() {
+            eprintln!("Failed to run palette reload command `{}`: {}", command, e);
+        }
+    }
+}
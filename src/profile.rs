@@ -0,0 +1,113 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::{config, fit::FitMode, gui, state, WallpaperBackend};
+
+const PROFILE_PREFIX: &str = "Profile ";
+const MONITOR_PREFIX: &str = "monitor.";
+
+pub fn names() -> Vec<String> {
+    config::sections()
+        .into_iter()
+        .filter_map(|section| {
+            section
+                .strip_prefix(PROFILE_PREFIX)
+                .map(|n| n.trim().to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+pub fn active() -> Option<String> {
+    state::get("profile")
+}
+
+fn validate(name: &str, settings: &[(String, String)]) -> Result<(), String> {
+    for (key, value) in settings {
+        let valid = match key.as_str() {
+            "backend" => WallpaperBackend::from_name(value)
+                .is_some_and(|backend| backend != WallpaperBackend::None),
+            "folder" => Path::new(&shellexpand::tilde(value).into_owned()).is_dir(),
+            "fit" => FitMode::from_name(value).is_some(),
+            "wallpaper" => Path::new(&shellexpand::tilde(value).into_owned()).exists(),
+            key if key.starts_with(MONITOR_PREFIX) => {
+                Path::new(&shellexpand::tilde(value).into_owned()).exists()
+            }
+            _ => true,
+        };
+        if !valid {
+            return Err(format!("Invalid {} in profile {}: {}", key, name, value));
+        }
+    }
+    Ok(())
+}
+
+fn apply_settings(name: &str, settings: &[(String, String)]) {
+    let mut overrides = BTreeMap::new();
+    for (key, value) in settings {
+        match key.as_str() {
+            key if state::PROFILE_KEYS.contains(&key) => {
+                overrides.insert(key.to_string(), value.clone());
+            }
+            "wallpaper" => {}
+            key if key.starts_with(MONITOR_PREFIX) => {}
+            other => eprintln!("Ignoring unknown profile setting: {}", other),
+        }
+    }
+    state::set_profile(name, &overrides);
+
+    if let Some(backend) = gui::load_wallpaper_backend() {
+        if backend != *crate::CURRENT_BACKEND.lock() {
+            crate::set_wallpaper_backend(backend);
+        }
+    }
+}
+
+pub async fn apply(name: &str) -> Result<(), String> {
+    if !names().iter().any(|profile| profile == name) {
+        return Err(format!("No profile named {}", name));
+    }
+
+    let settings = config::section(&format!("{}{}", PROFILE_PREFIX, name));
+    validate(name, &settings)?;
+    apply_settings(name, &settings);
+
+    let monitors: Vec<(String, String)> = settings
+        .iter()
+        .filter_map(|(key, path)| {
+            key.strip_prefix(MONITOR_PREFIX)
+                .map(|monitor| (monitor.to_string(), path.clone()))
+        })
+        .collect();
+    let wallpaper = settings
+        .iter()
+        .find(|(key, _)| key == "wallpaper")
+        .map(|(_, path)| path.clone());
+
+    if !monitors.is_empty() {
+        crate::set_monitor_wallpapers(&monitors).await?;
+        crate::monitor_wallpapers_changed(&monitors);
+    } else if let Some(wallpaper) = wallpaper {
+        crate::apply_wallpaper(&wallpaper).await?;
+    }
+
+    Ok(())
+}
+
+pub fn print_profiles() {
+    let names = names();
+    if names.is_empty() {
+        eprintln!("No profiles configured");
+        return;
+    }
+
+    let active = active();
+    for name in names {
+        let marker = if active.as_ref() == Some(&name) {
+            "*"
+        } else {
+            " "
+        };
+        println!("{} {}", marker, name);
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::{config, state, WallpaperBackend, CURRENT_BACKEND};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+pub struct Reload {
+    settings: BTreeMap<String, String>,
+    changed: Vec<String>,
+    pub errors: Vec<String>,
+}
+
+impl Reload {
+    pub fn value(&self, key: &str) -> Option<&str> {
+        if !self.is_changed(key) {
+            return None;
+        }
+        self.settings
+            .get(key)
+            .map(String::as_str)
+            .filter(|value| crate::validate_setting(key, value).is_ok())
+    }
+
+    fn is_changed(&self, key: &str) -> bool {
+        self.changed.iter().any(|changed| changed == key)
+    }
+
+    pub fn apply_backend(&self) -> bool {
+        let Some(backend) = self.value("backend").and_then(WallpaperBackend::from_name) else {
+            return false;
+        };
+        if backend == *CURRENT_BACKEND.lock() {
+            return false;
+        }
+
+        println!("Config changed, switching backend to {}", backend.name());
+        crate::set_wallpaper_backend(backend);
+        true
+    }
+}
+
+fn modified() -> [Option<SystemTime>; 2] {
+    [config::modified(), state::modified()]
+}
+
+pub struct ConfigWatcher {
+    modified: [Option<SystemTime>; 2],
+    settings: BTreeMap<String, String>,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> Self {
+        ConfigWatcher {
+            modified: modified(),
+            settings: config::settings(),
+        }
+    }
+
+    fn poll(&mut self) -> bool {
+        let modified = modified();
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+
+    pub async fn wait(&mut self, duration: Duration) -> bool {
+        let deadline = Instant::now() + duration;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return false;
+            }
+            tokio::time::sleep(remaining.min(POLL_INTERVAL)).await;
+            if self.poll() {
+                return true;
+            }
+        }
+    }
+
+    pub fn reload(&mut self) -> Reload {
+        self.modified = modified();
+        let settings = config::settings();
+
+        let changed: Vec<String> = settings
+            .keys()
+            .chain(self.settings.keys())
+            .filter(|key| settings.get(*key) != self.settings.get(*key))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+
+        let errors = changed
+            .iter()
+            .filter_map(|key| {
+                let value = settings.get(key)?;
+                crate::validate_setting(key, value)
+                    .err()
+                    .map(|e| format!("{} = {}: {}", key, value, e))
+            })
+            .collect();
+
+        self.settings = settings.clone();
+        Reload {
+            settings,
+            changed,
+            errors,
+        }
+    }
+}
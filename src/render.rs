@@ -0,0 +1,290 @@
+use gtk::gdk_pixbuf::{Colorspace, InterpType, Pixbuf};
+use std::path::Path;
+
+use crate::{cache, config, fit::FitMode, library, Monitor};
+
+pub const DEFAULT_FOCAL_POINT: (f64, f64) = (0.5, 0.5);
+
+pub fn parse_focal_point(value: &str) -> Option<(f64, f64)> {
+    let (x, y) = value.split_once(',')?;
+    let x: f64 = x.trim().parse().ok()?;
+    let y: f64 = y.trim().parse().ok()?;
+    ((0.0..=1.0).contains(&x) && (0.0..=1.0).contains(&y)).then_some((x, y))
+}
+
+pub fn prescale_enabled() -> bool {
+    config::get("prescale").is_some_and(|value| value == "true")
+}
+
+pub fn span_enabled() -> bool {
+    config::get("span").is_some_and(|value| value == "true")
+}
+
+fn span_gap() -> i32 {
+    config::get("span_gap")
+        .and_then(|gap| gap.parse().ok())
+        .unwrap_or(0)
+}
+
+pub fn focal_point(path: &str) -> (f64, f64) {
+    library::load()
+        .focal_point(path)
+        .or_else(|| config::get("focal_point").and_then(|value| parse_focal_point(&value)))
+        .unwrap_or(DEFAULT_FOCAL_POINT)
+}
+
+fn crop_offset(scaled: i32, target: i32, focal: f64) -> i32 {
+    let offset = (scaled as f64 * focal - target as f64 / 2.0).round() as i32;
+    offset.clamp(0, (scaled - target).max(0))
+}
+
+fn cover(pixbuf: &Pixbuf, width: i32, height: i32, focal: (f64, f64)) -> Option<Pixbuf> {
+    let scale = (width as f64 / pixbuf.width() as f64).max(height as f64 / pixbuf.height() as f64);
+    let scaled_width = ((pixbuf.width() as f64 * scale).ceil() as i32).max(width);
+    let scaled_height = ((pixbuf.height() as f64 * scale).ceil() as i32).max(height);
+
+    let scaled = pixbuf.scale_simple(scaled_width, scaled_height, InterpType::Hyper)?;
+    Some(scaled.new_subpixbuf(
+        crop_offset(scaled_width, width, focal.0),
+        crop_offset(scaled_height, height, focal.1),
+        width,
+        height,
+    ))
+}
+
+fn blank(width: i32, height: i32) -> Option<Pixbuf> {
+    let canvas = Pixbuf::new(Colorspace::Rgb, false, 8, width, height)?;
+    canvas.fill(0x000000ff);
+    Some(canvas)
+}
+
+fn place(pixbuf: &Pixbuf, canvas: &Pixbuf, x: f64, y: f64, scale: f64) {
+    let left = x.max(0.0).round() as i32;
+    let top = y.max(0.0).round() as i32;
+    let right = ((x + pixbuf.width() as f64 * scale).round() as i32).min(canvas.width());
+    let bottom = ((y + pixbuf.height() as f64 * scale).round() as i32).min(canvas.height());
+    if right > left && bottom > top {
+        pixbuf.composite(
+            canvas,
+            left,
+            top,
+            right - left,
+            bottom - top,
+            x,
+            y,
+            scale,
+            scale,
+            InterpType::Hyper,
+            255,
+        );
+    }
+}
+
+fn center_offset(size: i32, target: i32, focal: f64) -> f64 {
+    if size > target {
+        -crop_offset(size, target, focal) as f64
+    } else {
+        ((target - size) / 2) as f64
+    }
+}
+
+pub fn fit(
+    pixbuf: &Pixbuf,
+    width: i32,
+    height: i32,
+    focal: (f64, f64),
+    mode: FitMode,
+) -> Option<Pixbuf> {
+    match mode {
+        FitMode::Fill => cover(pixbuf, width, height, focal),
+        FitMode::Fit => {
+            let canvas = blank(width, height)?;
+            let scale =
+                (width as f64 / pixbuf.width() as f64).min(height as f64 / pixbuf.height() as f64);
+            let x = (width as f64 - pixbuf.width() as f64 * scale) / 2.0;
+            let y = (height as f64 - pixbuf.height() as f64 * scale) / 2.0;
+            place(pixbuf, &canvas, x, y, scale);
+            Some(canvas)
+        }
+        FitMode::Center => {
+            let canvas = blank(width, height)?;
+            let x = center_offset(pixbuf.width(), width, focal.0);
+            let y = center_offset(pixbuf.height(), height, focal.1);
+            place(pixbuf, &canvas, x, y, 1.0);
+            Some(canvas)
+        }
+        FitMode::Tile => {
+            let canvas = blank(width, height)?;
+            for y in (0..height).step_by(pixbuf.height().max(1) as usize) {
+                for x in (0..width).step_by(pixbuf.width().max(1) as usize) {
+                    place(pixbuf, &canvas, x as f64, y as f64, 1.0);
+                }
+            }
+            Some(canvas)
+        }
+    }
+}
+
+pub fn output_size(monitor: &Monitor) -> (i32, i32) {
+    if monitor.transform % 2 == 1 {
+        (monitor.height, monitor.width)
+    } else {
+        (monitor.width, monitor.height)
+    }
+}
+
+struct Region {
+    x: i32,
+    y: i32,
+    width: i32,
+    height: i32,
+}
+
+fn span_layout(monitors: &[Monitor], gap: i32, scale: f64) -> (Vec<Region>, i32, i32) {
+    let logical: Vec<(i32, i32, i32, i32)> = monitors
+        .iter()
+        .map(|monitor| {
+            let (width, height) = output_size(monitor);
+            let monitor_scale = if monitor.scale > 0.0 {
+                monitor.scale
+            } else {
+                1.0
+            };
+            (
+                monitor.x,
+                monitor.y,
+                (width as f64 / monitor_scale).round() as i32,
+                (height as f64 / monitor_scale).round() as i32,
+            )
+        })
+        .collect();
+
+    let columns = |x: i32| logical.iter().filter(|m| m.0 + m.2 <= x).count() as i32;
+    let rows = |y: i32| logical.iter().filter(|m| m.1 + m.3 <= y).count() as i32;
+
+    let shifted: Vec<(i32, i32, i32, i32)> = logical
+        .iter()
+        .map(|&(x, y, width, height)| (x + columns(x) * gap, y + rows(y) * gap, width, height))
+        .collect();
+
+    let min_x = shifted.iter().map(|m| m.0).min().unwrap_or(0);
+    let min_y = shifted.iter().map(|m| m.1).min().unwrap_or(0);
+    let max_x = shifted.iter().map(|m| m.0 + m.2).max().unwrap_or(0);
+    let max_y = shifted.iter().map(|m| m.1 + m.3).max().unwrap_or(0);
+
+    let to_canvas = |value: i32| (value as f64 * scale).round() as i32;
+    let regions = shifted
+        .iter()
+        .map(|&(x, y, width, height)| Region {
+            x: to_canvas(x - min_x),
+            y: to_canvas(y - min_y),
+            width: to_canvas(width).max(1),
+            height: to_canvas(height).max(1),
+        })
+        .collect();
+
+    (regions, to_canvas(max_x - min_x), to_canvas(max_y - min_y))
+}
+
+pub fn span(path: &str, monitors: &[Monitor]) -> Result<Vec<(String, String)>, String> {
+    let gap = span_gap();
+    let scale = monitors
+        .iter()
+        .map(|monitor| monitor.scale)
+        .fold(1.0, f64::max);
+    let (regions, canvas_width, canvas_height) = span_layout(monitors, gap, scale);
+    if canvas_width <= 0 || canvas_height <= 0 {
+        return Err("Invalid monitor layout".to_string());
+    }
+
+    let focal = focal_point(&path.replace(&std::env::var("HOME").unwrap_or_default(), "~"));
+    let mode = FitMode::load();
+    let hash = cache::content_hash(Path::new(path))?;
+    let mut canvas: Option<Pixbuf> = None;
+    let mut slices = Vec::new();
+
+    for (monitor, region) in monitors.iter().zip(&regions) {
+        let (width, height) = output_size(monitor);
+        let key = format!(
+            "{}-span-{}-{}x{}-{},{}-{}x{}-{}x{}-{:.3}-{:.3}",
+            hash,
+            mode.name(),
+            canvas_width,
+            canvas_height,
+            region.x,
+            region.y,
+            region.width,
+            region.height,
+            width,
+            height,
+            focal.0,
+            focal.1
+        );
+
+        let slice = cache::get_or_create("renditions", &key, "png", |target| {
+            if canvas.is_none() {
+                println!(
+                    "Rendering {} across {} monitors ({}x{})",
+                    path,
+                    monitors.len(),
+                    canvas_width,
+                    canvas_height
+                );
+                let pixbuf = Pixbuf::from_file(path)
+                    .map_err(|e| format!("Failed to load {}: {}", path, e))?;
+                let pixbuf = pixbuf.apply_embedded_orientation().unwrap_or(pixbuf);
+                canvas = Some(
+                    fit(&pixbuf, canvas_width, canvas_height, focal, mode)
+                        .ok_or_else(|| format!("Failed to scale {}", path))?,
+                );
+            }
+
+            let canvas = canvas.as_ref().ok_or("Failed to render span")?;
+            let region_width = region.width.min(canvas_width - region.x);
+            let region_height = region.height.min(canvas_height - region.y);
+            canvas
+                .new_subpixbuf(region.x, region.y, region_width, region_height)
+                .scale_simple(width.max(1), height.max(1), InterpType::Hyper)
+                .ok_or_else(|| format!("Failed to scale slice for {}", monitor.name))?
+                .savev(target, "png", &[])
+                .map_err(|e| format!("Failed to save slice for {}: {}", monitor.name, e))
+        })?;
+
+        slices.push((monitor.name.clone(), slice));
+    }
+
+    Ok(slices)
+}
+
+pub fn rendition(path: &str, monitor: &Monitor) -> Result<String, String> {
+    let (width, height) = output_size(monitor);
+    if width <= 0 || height <= 0 {
+        return Err(format!("Invalid size for monitor {}", monitor.name));
+    }
+
+    let focal = focal_point(&path.replace(&std::env::var("HOME").unwrap_or_default(), "~"));
+    let mode = FitMode::load();
+    let key = format!(
+        "{}-{}-{}x{}-{:.3}-{:.3}",
+        cache::content_hash(Path::new(path))?,
+        mode.name(),
+        width,
+        height,
+        focal.0,
+        focal.1
+    );
+
+    cache::get_or_create("renditions", &key, "png", |target| {
+        println!(
+            "Rendering {} for {} ({}x{})",
+            path, monitor.name, width, height
+        );
+        let pixbuf =
+            Pixbuf::from_file(path).map_err(|e| format!("Failed to load {}: {}", path, e))?;
+        let pixbuf = pixbuf.apply_embedded_orientation().unwrap_or(pixbuf);
+        fit(&pixbuf, width, height, focal, mode)
+            .ok_or_else(|| format!("Failed to scale {}", path))?
+            .savev(target, "png", &[])
+            .map_err(|e| format!("Failed to save rendition of {}: {}", path, e))
+    })
+}
@@ -0,0 +1,279 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Utc};
+use std::f64::consts::PI;
+use std::path::Path;
+
+use crate::{config, reload::ConfigWatcher, shuffle, slideshow};
+
+const SCHEDULE_SECTION: &str = "Schedule";
+const MAX_SLEEP_SECS: i64 = 60;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TimeBase {
+    Clock(NaiveTime),
+    Sunrise,
+    Sunset,
+}
+
+#[derive(Clone, Debug)]
+pub struct ScheduleEntry {
+    pub spec: String,
+    pub target: String,
+    base: TimeBase,
+    offset: TimeDelta,
+}
+
+fn parse_clock(clock: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(clock, "%H:%M").ok()
+}
+
+fn parse_spec(spec: &str) -> Option<(TimeBase, TimeDelta)> {
+    let spec = spec.trim().to_lowercase();
+
+    for (name, base) in [("sunrise", TimeBase::Sunrise), ("sunset", TimeBase::Sunset)] {
+        if let Some(rest) = spec.strip_prefix(name) {
+            if rest.is_empty() {
+                return Some((base, TimeDelta::zero()));
+            }
+
+            let (sign, clock) = rest.split_at(1);
+            let offset = parse_clock(clock)?.signed_duration_since(NaiveTime::MIN);
+            return match sign {
+                "+" => Some((base, offset)),
+                "-" => Some((base, -offset)),
+                _ => None,
+            };
+        }
+    }
+
+    parse_clock(&spec).map(|time| (TimeBase::Clock(time), TimeDelta::zero()))
+}
+
+fn section_name() -> String {
+    match config::get("schedule") {
+        Some(name) if !name.is_empty() && name != "default" => {
+            format!("{} {}", SCHEDULE_SECTION, name)
+        }
+        _ => SCHEDULE_SECTION.to_string(),
+    }
+}
+
+pub fn load() -> Vec<ScheduleEntry> {
+    config::section(&section_name())
+        .into_iter()
+        .filter_map(|(spec, target)| match parse_spec(&spec) {
+            Some((base, offset)) => Some(ScheduleEntry {
+                spec,
+                target,
+                base,
+                offset,
+            }),
+            None => {
+                eprintln!("Ignoring invalid schedule time: {}", spec);
+                None
+            }
+        })
+        .collect()
+}
+
+fn location() -> Option<(f64, f64)> {
+    let latitude = config::get("latitude")?.parse().ok()?;
+    let longitude = config::get("longitude")?.parse().ok()?;
+    Some((latitude, longitude))
+}
+
+fn sun_times(
+    date: NaiveDate,
+    latitude: f64,
+    longitude: f64,
+) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    let to_radians = |deg: f64| deg * PI / 180.0;
+    let to_degrees = |rad: f64| rad * 180.0 / PI;
+
+    let julian_date =
+        date.and_time(NaiveTime::MIN).and_utc().timestamp() as f64 / 86400.0 + 2440587.5;
+    let day = (julian_date - 2451545.0 + 0.0008).ceil();
+    let mean_solar_time = day - longitude / 360.0;
+
+    let anomaly = (357.5291 + 0.98560028 * mean_solar_time).rem_euclid(360.0);
+    let center = 1.9148 * to_radians(anomaly).sin()
+        + 0.0200 * to_radians(2.0 * anomaly).sin()
+        + 0.0003 * to_radians(3.0 * anomaly).sin();
+    let ecliptic_longitude = (anomaly + center + 180.0 + 102.9372).rem_euclid(360.0);
+    let transit = 2451545.0 + mean_solar_time + 0.0053 * to_radians(anomaly).sin()
+        - 0.0069 * to_radians(2.0 * ecliptic_longitude).sin();
+
+    let declination = (to_radians(ecliptic_longitude).sin() * to_radians(23.4397).sin()).asin();
+    let latitude = to_radians(latitude);
+    let cos_hour_angle = (to_radians(-0.833).sin() - latitude.sin() * declination.sin())
+        / (latitude.cos() * declination.cos());
+
+    if !(-1.0..=1.0).contains(&cos_hour_angle) {
+        return None;
+    }
+
+    let hour_angle = to_degrees(cos_hour_angle.acos());
+    let to_local = |julian: f64| {
+        DateTime::<Utc>::from_timestamp(((julian - 2440587.5) * 86400.0) as i64, 0)
+            .map(|utc| utc.with_timezone(&Local).naive_local())
+    };
+
+    Some((
+        to_local(transit - hour_angle / 360.0)?,
+        to_local(transit + hour_angle / 360.0)?,
+    ))
+}
+
+impl ScheduleEntry {
+    pub fn resolve(&self, date: NaiveDate) -> Option<NaiveDateTime> {
+        let base = match self.base {
+            TimeBase::Clock(time) => date.and_time(time),
+            TimeBase::Sunrise | TimeBase::Sunset => {
+                let (latitude, longitude) = location()?;
+                let (sunrise, sunset) = sun_times(date, latitude, longitude)?;
+                if self.base == TimeBase::Sunrise {
+                    sunrise
+                } else {
+                    sunset
+                }
+            }
+        };
+        Some(base + self.offset)
+    }
+}
+
+fn active_entry(entries: &[ScheduleEntry], now: NaiveDateTime) -> Option<(usize, NaiveDateTime)> {
+    let today = now.date();
+    [today.pred_opt(), Some(today)]
+        .into_iter()
+        .flatten()
+        .flat_map(|date| {
+            entries
+                .iter()
+                .enumerate()
+                .filter_map(move |(i, entry)| entry.resolve(date).map(|start| (i, start)))
+        })
+        .filter(|(_, start)| *start <= now)
+        .max_by_key(|(_, start)| *start)
+}
+
+fn next_change(entries: &[ScheduleEntry], now: NaiveDateTime) -> Option<NaiveDateTime> {
+    let today = now.date();
+    [Some(today), today.succ_opt()]
+        .into_iter()
+        .flatten()
+        .flat_map(|date| entries.iter().filter_map(move |entry| entry.resolve(date)))
+        .filter(|start| *start > now)
+        .min()
+}
+
+async fn apply_target(target: &str) -> Result<String, String> {
+    let expanded = shellexpand::tilde(target).into_owned();
+    let path = if Path::new(&expanded).is_dir() {
+        let wallpapers = crate::get_wallpapers_in(Path::new(&expanded)).await?;
+        shuffle::next(&shuffle::source(Path::new(&expanded), None), &wallpapers)
+            .ok_or_else(|| "No wallpapers found".to_string())?
+    } else {
+        target.to_string()
+    };
+
+    crate::apply_wallpaper(&path).await?;
+    Ok(path)
+}
+
+pub fn print_schedule() {
+    let entries = load();
+    if entries.is_empty() {
+        eprintln!("No schedule configured");
+        return;
+    }
+
+    let today = Local::now().date_naive();
+    for entry in entries {
+        let start = entry
+            .resolve(today)
+            .map(|start| start.format("%H:%M").to_string())
+            .unwrap_or_else(|| "--:--".to_string());
+        println!("{}  {}  {}", start, entry.spec, entry.target);
+    }
+}
+
+async fn update_slideshow(applied: &mut Option<String>) -> Option<i64> {
+    let current = crate::gui::load_last_wallpaper()?;
+    let expanded = shellexpand::tilde(&current).into_owned();
+    if !slideshow::is_slideshow(Path::new(&expanded)) {
+        *applied = None;
+        return None;
+    }
+
+    let (file, remaining) = match slideshow::load(Path::new(&expanded)) {
+        Ok(slideshow) => slideshow.current(),
+        Err(e) => {
+            eprintln!("Error loading slideshow: {}", e);
+            return None;
+        }
+    };
+
+    if applied.as_ref() != Some(&file) {
+        match crate::set_wallpaper_internal(&current).await {
+            Ok(_) => {
+                println!("Slideshow wallpaper set: {}", file);
+                *applied = Some(file);
+            }
+            Err(e) => eprintln!("Error setting slideshow wallpaper: {}", e),
+        }
+    }
+
+    Some(remaining.ceil() as i64)
+}
+
+pub async fn run() {
+    let mut applied = None;
+    let mut applied_slide = None;
+    let mut watcher = ConfigWatcher::new();
+
+    if load().is_empty() {
+        println!("No schedule configured, only timed slideshows will be updated");
+    }
+
+    loop {
+        let entries = load();
+        let now = Local::now().naive_local();
+
+        if let Some((index, start)) = active_entry(&entries, now) {
+            let key = (entries[index].spec.clone(), start);
+            if applied.as_ref() != Some(&key) {
+                match apply_target(&entries[index].target).await {
+                    Ok(path) => {
+                        println!("Scheduled wallpaper set ({}): {}", key.0, path);
+                        applied = Some(key);
+                        applied_slide = None;
+                    }
+                    Err(e) => eprintln!("Error setting scheduled wallpaper: {}", e),
+                }
+            }
+        }
+
+        let slide_change = update_slideshow(&mut applied_slide).await;
+        let schedule_change = next_change(&entries, now).map(|next| (next - now).num_seconds());
+
+        let sleep = [schedule_change, slide_change]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(MAX_SLEEP_SECS)
+            .clamp(1, MAX_SLEEP_SECS);
+        if watcher
+            .wait(tokio::time::Duration::from_secs(sleep as u64))
+            .await
+        {
+            let reload = watcher.reload();
+            for error in &reload.errors {
+                eprintln!("Ignoring invalid config value {}", error);
+            }
+            if reload.apply_backend() {
+                applied = None;
+                applied_slide = None;
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use rand::{seq::SliceRandom, Rng};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::Path;
+
+use crate::{atomic, config, history, library, state};
+
+const SHUFFLE_FILE: &str = "shuffle.json";
+const FAVORITE_COPIES: usize = 2;
+const RECENT_WEIGHT: f64 = 0.25;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Weighting {
+    None,
+    Favorites,
+    Recency,
+}
+
+impl Weighting {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "none" => Some(Weighting::None),
+            "favorites" => Some(Weighting::Favorites),
+            "recency" => Some(Weighting::Recency),
+            _ => None,
+        }
+    }
+
+    fn load() -> Self {
+        config::get("random_weight")
+            .and_then(|weight| Weighting::from_name(&weight))
+            .unwrap_or(Weighting::None)
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ShuffleBag {
+    remaining: Vec<String>,
+    last: Option<String>,
+}
+
+fn shuffle_path() -> String {
+    state::file(SHUFFLE_FILE).to_string_lossy().into_owned()
+}
+
+fn load() -> BTreeMap<String, ShuffleBag> {
+    fs::read_to_string(shuffle_path())
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save(bags: &BTreeMap<String, ShuffleBag>) {
+    if let Ok(json) = serde_json::to_string(bags) {
+        if let Err(e) = atomic::write(Path::new(&shuffle_path()), json.as_bytes()) {
+            eprintln!("Error saving shuffle state: {}", e);
+        }
+    }
+}
+
+fn fill(candidates: &[String], weighting: Weighting) -> Vec<String> {
+    let mut rng = rand::thread_rng();
+
+    match weighting {
+        Weighting::None => {
+            let mut bag = candidates.to_vec();
+            bag.shuffle(&mut rng);
+            bag
+        }
+        Weighting::Favorites => {
+            let library = library::load();
+            let mut bag: Vec<String> = candidates
+                .iter()
+                .flat_map(|path| {
+                    let copies = if library.is_favorite(path) {
+                        FAVORITE_COPIES
+                    } else {
+                        1
+                    };
+                    std::iter::repeat_n(path.clone(), copies)
+                })
+                .collect();
+            bag.shuffle(&mut rng);
+            bag
+        }
+        Weighting::Recency => {
+            let recent: HashSet<String> = history::load().into_iter().map(|e| e.path).collect();
+            let mut keyed: Vec<(f64, String)> = candidates
+                .iter()
+                .map(|path| {
+                    let weight = if recent.contains(path) {
+                        RECENT_WEIGHT
+                    } else {
+                        1.0
+                    };
+                    (rng.gen::<f64>().powf(1.0 / weight), path.clone())
+                })
+                .collect();
+            keyed.sort_by(|a, b| b.0.total_cmp(&a.0));
+            keyed.into_iter().map(|(_, path)| path).collect()
+        }
+    }
+}
+
+pub fn source(folder: &Path, tag: Option<&str>) -> String {
+    let folder = folder.to_string_lossy();
+    match tag {
+        Some(tag) => format!("{}#{}", folder, tag),
+        None => folder.into_owned(),
+    }
+}
+
+pub fn favorites_source(folder: &Path) -> String {
+    format!("{}!favorites", folder.to_string_lossy())
+}
+
+pub fn next(source: &str, candidates: &[String]) -> Option<String> {
+    if candidates.is_empty() {
+        return None;
+    }
+
+    atomic::with_lock(Path::new(&shuffle_path()), || {
+        next_locked(source, candidates)
+    })
+}
+
+fn next_locked(source: &str, candidates: &[String]) -> Option<String> {
+    let candidate_set: HashSet<&String> = candidates.iter().collect();
+    let mut bags = load();
+    let bag = bags.entry(source.to_string()).or_default();
+    bag.remaining.retain(|path| candidate_set.contains(path));
+
+    if bag.remaining.is_empty() {
+        bag.remaining = fill(candidates, Weighting::load());
+    }
+
+    let index = bag
+        .remaining
+        .iter()
+        .position(|path| Some(path) != bag.last.as_ref())
+        .unwrap_or(0);
+    let pick = bag.remaining.remove(index);
+    bag.last = Some(pick.clone());
+    save(&bags);
+    Some(pick)
+}
@@ -0,0 +1,140 @@
+use chrono::{Local, NaiveDate, NaiveDateTime};
+use roxmltree::{Document, Node};
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Debug)]
+struct Frame {
+    file: String,
+    duration: f64,
+    transition_to: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Slideshow {
+    start: NaiveDateTime,
+    frames: Vec<Frame>,
+}
+
+pub fn is_slideshow(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("xml"))
+}
+
+fn child<'a>(node: Node<'a, 'a>, name: &str) -> Option<Node<'a, 'a>> {
+    node.children().find(|n| n.has_tag_name(name))
+}
+
+fn child_text<'a>(node: Node<'a, 'a>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|n| n.text()).map(str::trim)
+}
+
+fn child_number<T: std::str::FromStr>(node: Node, name: &str) -> Option<T> {
+    child_text(node, name).and_then(|text| text.parse().ok())
+}
+
+fn file_path(node: Node, name: &str, base: &Path) -> Option<String> {
+    let file = child(node, name)?;
+    let text = file
+        .text()
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .or_else(|| child_text(file, "size"))?;
+    Some(base.join(text).to_string_lossy().into_owned())
+}
+
+pub fn load(path: &Path) -> Result<Slideshow, String> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| format!("Failed to read slideshow: {}", e))?;
+    let document =
+        Document::parse(&contents).map_err(|e| format!("Failed to parse slideshow: {}", e))?;
+    let root = document.root_element();
+
+    if !root.has_tag_name("background") {
+        return Err("Not a timed wallpaper slideshow".to_string());
+    }
+
+    let start = child(root, "starttime")
+        .and_then(|node| {
+            NaiveDate::from_ymd_opt(
+                child_number(node, "year")?,
+                child_number(node, "month")?,
+                child_number(node, "day")?,
+            )?
+            .and_hms_opt(
+                child_number(node, "hour").unwrap_or(0),
+                child_number(node, "minute").unwrap_or(0),
+                child_number(node, "second").unwrap_or(0),
+            )
+        })
+        .unwrap_or_default();
+
+    let base = path.parent().unwrap_or(Path::new("/"));
+    let mut frames = Vec::new();
+
+    for node in root.children().filter(|n| n.is_element()) {
+        let duration = child_number(node, "duration").unwrap_or(0.0);
+        match node.tag_name().name() {
+            "static" => {
+                if let Some(file) = file_path(node, "file", base) {
+                    frames.push(Frame {
+                        file,
+                        duration,
+                        transition_to: None,
+                    });
+                }
+            }
+            "transition" => {
+                if let Some(from) = file_path(node, "from", base) {
+                    frames.push(Frame {
+                        file: from,
+                        duration,
+                        transition_to: file_path(node, "to", base),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if frames.is_empty() || frames.iter().all(|frame| frame.duration <= 0.0) {
+        return Err("Slideshow has no images".to_string());
+    }
+
+    Ok(Slideshow { start, frames })
+}
+
+impl Slideshow {
+    pub fn first_file(&self) -> &str {
+        &self.frames[0].file
+    }
+
+    pub fn current(&self) -> (String, f64) {
+        let total: f64 = self.frames.iter().map(|frame| frame.duration).sum();
+        let elapsed = (Local::now().naive_local() - self.start).num_milliseconds() as f64 / 1000.0;
+        let mut position = elapsed.rem_euclid(total);
+
+        for frame in &self.frames {
+            if position < frame.duration {
+                let remaining = frame.duration - position;
+                return match &frame.transition_to {
+                    Some(to) if position >= frame.duration / 2.0 => (to.clone(), remaining),
+                    Some(_) => (frame.file.clone(), frame.duration / 2.0 - position),
+                    None => (frame.file.clone(), remaining),
+                };
+            }
+            position -= frame.duration;
+        }
+
+        (self.frames[0].file.clone(), self.frames[0].duration)
+    }
+}
+
+pub fn resolve(path: &str) -> Result<String, String> {
+    if is_slideshow(Path::new(path)) {
+        load(Path::new(path)).map(|slideshow| slideshow.current().0)
+    } else {
+        Ok(path.to_string())
+    }
+}
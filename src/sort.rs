@@ -0,0 +1,184 @@
+use gtk::gdk_pixbuf::Pixbuf;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::{config, palette};
+
+const GRAY_SATURATION: f64 = 0.15;
+const GRAY_OFFSET: u64 = 360_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    Name,
+    Natural,
+    Modified,
+    Size,
+    Resolution,
+    Aspect,
+    Color,
+}
+
+impl SortOrder {
+    pub const ALL: [SortOrder; 7] = [
+        SortOrder::Name,
+        SortOrder::Natural,
+        SortOrder::Modified,
+        SortOrder::Size,
+        SortOrder::Resolution,
+        SortOrder::Aspect,
+        SortOrder::Color,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "name" => Some(SortOrder::Name),
+            "natural" => Some(SortOrder::Natural),
+            "mtime" => Some(SortOrder::Modified),
+            "size" => Some(SortOrder::Size),
+            "resolution" => Some(SortOrder::Resolution),
+            "aspect" => Some(SortOrder::Aspect),
+            "color" => Some(SortOrder::Color),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SortOrder::Name => "name",
+            SortOrder::Natural => "natural",
+            SortOrder::Modified => "mtime",
+            SortOrder::Size => "size",
+            SortOrder::Resolution => "resolution",
+            SortOrder::Aspect => "aspect",
+            SortOrder::Color => "color",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortOrder::Name => "Name",
+            SortOrder::Natural => "Natural name",
+            SortOrder::Modified => "Date modified",
+            SortOrder::Size => "File size",
+            SortOrder::Resolution => "Resolution",
+            SortOrder::Aspect => "Aspect ratio",
+            SortOrder::Color => "Dominant color",
+        }
+    }
+
+    pub fn load() -> Self {
+        config::get("sort")
+            .and_then(|sort| SortOrder::from_name(&sort))
+            .unwrap_or(SortOrder::Name)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Chunk {
+    Number(usize, String),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SortKey {
+    Value(u64),
+    Natural(Vec<Chunk>),
+    Text(String),
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn natural_chunks(name: &str) -> Vec<Chunk> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for c in name.chars() {
+        let digit = c.is_ascii_digit();
+        if current
+            .chars()
+            .next()
+            .is_some_and(|first| first.is_ascii_digit() != digit)
+        {
+            chunks.push(chunk(std::mem::take(&mut current)));
+        }
+        current.push(c);
+    }
+    if !current.is_empty() {
+        chunks.push(chunk(current));
+    }
+    chunks
+}
+
+fn chunk(text: String) -> Chunk {
+    if text.starts_with(|c: char| c.is_ascii_digit()) {
+        let digits = text.trim_start_matches('0').to_string();
+        Chunk::Number(digits.len(), digits)
+    } else {
+        Chunk::Text(text.to_lowercase())
+    }
+}
+
+fn dimensions(path: &Path) -> Option<(u64, u64)> {
+    let (_, width, height) = Pixbuf::file_info(path)?;
+    (width > 0 && height > 0).then_some((width as u64, height as u64))
+}
+
+fn color_value(color: palette::Rgb) -> u64 {
+    let [r, g, b] = color.map(|c| c as f64 / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    let lightness = (max + min) / 2.0;
+    let saturation = if delta == 0.0 {
+        0.0
+    } else {
+        delta / (1.0 - (2.0 * lightness - 1.0).abs())
+    };
+
+    if saturation < GRAY_SATURATION {
+        return GRAY_OFFSET + (lightness * 1000.0) as u64;
+    }
+
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (hue * 1000.0) as u64
+}
+
+fn value(value: Option<u64>) -> SortKey {
+    SortKey::Value(value.unwrap_or(u64::MAX))
+}
+
+pub fn key(order: SortOrder, path: &Path) -> SortKey {
+    let metadata = || fs::metadata(path).ok();
+
+    match order {
+        SortOrder::Name => SortKey::Text(file_name(path)),
+        SortOrder::Natural => SortKey::Natural(natural_chunks(&file_name(path))),
+        SortOrder::Modified => value(
+            metadata()
+                .and_then(|m| m.modified().ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_micros() as u64),
+        ),
+        SortOrder::Size => value(metadata().map(|m| m.len())),
+        SortOrder::Resolution => value(dimensions(path).map(|(width, height)| width * height)),
+        SortOrder::Aspect => {
+            value(dimensions(path).map(|(width, height)| width * 1_000_000 / height))
+        }
+        SortOrder::Color => value(
+            palette::dominant(&path.to_string_lossy())
+                .ok()
+                .map(color_value),
+        ),
+    }
+}
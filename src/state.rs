@@ -0,0 +1,235 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{atomic, config, Assignment, WallpaperBackend};
+
+const CURRENT_LINK: &str = "current";
+const STATE_FILE: &str = "state.json";
+const RUNTIME_FILE: &str = "runtime.json";
+const OLD_STATE_DIR: &str = "~/.config/hyprwall";
+const MIGRATED_KEYS: [&str; 3] = ["last_wallpaper", "cursor", "profile"];
+const MIGRATED_FILES: [&str; 2] = ["history.json", "shuffle.json"];
+const PROFILE_SETTINGS: &str = "profile_settings";
+pub const PROFILE_KEYS: [&str; 4] = ["backend", "folder", "fit", "schedule"];
+
+#[derive(Serialize)]
+struct MonitorState<'a> {
+    name: &'a str,
+    image: &'a str,
+}
+
+#[derive(Serialize)]
+struct CurrentState<'a> {
+    wallpaper: &'a str,
+    image: &'a str,
+    backend: &'a str,
+    timestamp: u64,
+    monitors: Vec<MonitorState<'a>>,
+}
+
+pub fn state_dir() -> PathBuf {
+    std::env::var("XDG_STATE_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(shellexpand::tilde("~/.local/state").into_owned()))
+        .join("hyprwall")
+}
+
+pub fn file(name: &str) -> PathBuf {
+    state_dir().join(name)
+}
+
+fn load_runtime() -> BTreeMap<String, String> {
+    atomic::load_json(&file(RUNTIME_FILE)).unwrap_or_else(|e| {
+        eprintln!("Error loading state: {}", e);
+        BTreeMap::new()
+    })
+}
+
+fn load_runtime_for_update() -> Option<BTreeMap<String, String>> {
+    atomic::recover_json(&file(RUNTIME_FILE))
+        .map_err(|e| eprintln!("Error loading state, not saving changes: {}", e))
+        .ok()
+}
+
+fn save_runtime(values: &BTreeMap<String, String>) -> Result<(), String> {
+    let dir = state_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let json = serde_json::to_string_pretty(values)
+        .map_err(|e| format!("Failed to serialize state: {}", e))?;
+    atomic::write(&dir.join(RUNTIME_FILE), json.as_bytes())
+}
+
+pub fn get(key: &str) -> Option<String> {
+    load_runtime().remove(key)
+}
+
+pub fn set(key: &str, value: &str) {
+    set_all(&[(key, value)]);
+}
+
+pub fn set_all(entries: &[(&str, &str)]) {
+    atomic::with_lock(&file(RUNTIME_FILE), || {
+        let Some(mut values) = load_runtime_for_update() else {
+            return;
+        };
+        if entries
+            .iter()
+            .all(|(key, value)| values.get(*key).map(String::as_str) == Some(*value))
+        {
+            return;
+        }
+
+        for (key, value) in entries {
+            values.insert(key.to_string(), value.to_string());
+        }
+        if let Err(e) = save_runtime(&values) {
+            eprintln!("Error saving state: {}", e);
+        }
+    });
+}
+
+pub fn modified() -> Option<SystemTime> {
+    fs::metadata(file(RUNTIME_FILE))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+pub fn profile_settings() -> BTreeMap<String, String> {
+    get(PROFILE_SETTINGS)
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+pub fn set_profile(name: &str, settings: &BTreeMap<String, String>) {
+    match serde_json::to_string(settings) {
+        Ok(json) => set_all(&[("profile", name), (PROFILE_SETTINGS, &json)]),
+        Err(e) => eprintln!("Error serializing profile settings: {}", e),
+    }
+}
+
+pub fn clear_profile_setting(key: &str) {
+    let mut settings = profile_settings();
+    if settings.remove(key).is_none() {
+        return;
+    }
+    match serde_json::to_string(&settings) {
+        Ok(json) => set(PROFILE_SETTINGS, &json),
+        Err(e) => eprintln!("Error serializing profile settings: {}", e),
+    }
+}
+
+pub fn migrate() {
+    let migrated = atomic::with_lock(&file(RUNTIME_FILE), || {
+        let Some(mut values) = load_runtime_for_update() else {
+            return false;
+        };
+        let mut changed = false;
+
+        for key in MIGRATED_KEYS {
+            if let Some(value) = config::get(key) {
+                if value != "none" {
+                    values.entry(key.to_string()).or_insert(value);
+                    changed = true;
+                }
+            }
+        }
+
+        if changed {
+            if let Err(e) = save_runtime(&values) {
+                eprintln!("Error migrating state: {}", e);
+                return false;
+            }
+        }
+        true
+    });
+    if !migrated {
+        return;
+    }
+    for key in MIGRATED_KEYS {
+        config::remove(key);
+    }
+
+    let old_dir = PathBuf::from(shellexpand::tilde(OLD_STATE_DIR).into_owned());
+    for name in MIGRATED_FILES {
+        let old = old_dir.join(name);
+        let new = file(name);
+        if !old.exists() || new.exists() {
+            continue;
+        }
+
+        let _ = fs::create_dir_all(state_dir());
+        let moved = fs::rename(&old, &new)
+            .or_else(|_| fs::copy(&old, &new).and_then(|_| fs::remove_file(&old)));
+        match moved {
+            Ok(_) => eprintln!("Moved {} to {}", old.display(), new.display()),
+            Err(e) => eprintln!("Error moving {}: {}", old.display(), e),
+        }
+    }
+}
+
+fn monitor_link(dir: &Path, monitor: &str) -> PathBuf {
+    dir.join(format!("{}-{}", CURRENT_LINK, monitor))
+}
+
+fn publish(
+    wallpaper: &str,
+    assignments: &[Assignment],
+    backend: WallpaperBackend,
+) -> Result<(), String> {
+    let dir = state_dir();
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+
+    atomic::replace_symlink(&dir.join(CURRENT_LINK), wallpaper)?;
+
+    let monitors: Vec<MonitorState> = assignments
+        .iter()
+        .filter_map(|assignment| {
+            Some(MonitorState {
+                name: assignment.monitor.as_deref()?,
+                image: &assignment.path,
+            })
+        })
+        .collect();
+
+    for monitor in &monitors {
+        atomic::replace_symlink(&monitor_link(&dir, monitor.name), monitor.image)?;
+    }
+
+    let prefix = format!("{}-", CURRENT_LINK);
+    if let Ok(entries) = fs::read_dir(&dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stale = name
+                .strip_prefix(&prefix)
+                .is_some_and(|monitor| !monitors.iter().any(|m| m.name == monitor));
+            if stale && entry.path().is_symlink() {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+
+    let state = CurrentState {
+        wallpaper,
+        image: &assignments[0].path,
+        backend: backend.name(),
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        monitors,
+    };
+    let json = serde_json::to_string_pretty(&state)
+        .map_err(|e| format!("Failed to serialize state: {}", e))?;
+    atomic::write(&dir.join(STATE_FILE), json.as_bytes())
+}
+
+pub fn update(wallpaper: &str, assignments: &[Assignment], backend: WallpaperBackend) {
+    if let Err(e) = publish(wallpaper, assignments, backend) {
+        eprintln!("Error updating wallpaper state: {}", e);
+    }
+}